@@ -0,0 +1,210 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+use crate::Dividend;
+
+/// Slovenian tax rate on dividends.
+pub const DIVIDEND_TAX_RATE: Decimal = Decimal::from_parts(25, 0, 0, false, 2);
+
+/// The tax owed on a single dividend, in EUR.
+#[derive(Serialize)]
+pub struct DividendTax {
+    pub date: String,
+    pub payer: String,
+    pub payer_id: String,
+    pub source_country: String,
+    pub gross: Decimal,
+    /// Foreign tax claimed in the return, already limited to the treaty rate.
+    pub foreign_tax: Decimal,
+    pub slovenian_tax: Decimal,
+    /// Foreign tax credited, at most the Slovenian tax on the dividend.
+    pub credit: Decimal,
+    pub due: Decimal,
+    /// Relief is claimed under the double taxation convention, so no Slovenian tax is due.
+    pub relief: bool,
+}
+
+#[derive(Default, Serialize)]
+pub struct TaxTotals {
+    pub gross: Decimal,
+    pub foreign_tax: Decimal,
+    pub slovenian_tax: Decimal,
+    pub credit: Decimal,
+    pub due: Decimal,
+}
+
+/// The expected outcome of a Doh-Div return.
+#[derive(Serialize)]
+pub struct TaxCalculation {
+    pub year: i32,
+    pub rate: Decimal,
+    pub dividends: Vec<DividendTax>,
+    pub totals: TaxTotals,
+}
+
+/// Computes the Slovenian tax on each dividend and the foreign tax credited against it. Dividends
+/// claiming treaty relief are exempt and owe no Slovenian tax.
+pub fn calculate(year: i32, dividends: &[Dividend]) -> TaxCalculation {
+    let mut totals = TaxTotals::default();
+    let dividends: Vec<_> = dividends
+        .iter()
+        .map(|dividend| {
+            let gross = dividend.amount.round_cents().amount;
+            let foreign_tax = dividend.tax.round_cents().amount;
+            let relief = dividend.relief.is_some();
+            let slovenian_tax = if relief {
+                Decimal::ZERO
+            } else {
+                (gross * DIVIDEND_TAX_RATE)
+                    .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+            };
+            let credit = foreign_tax.min(slovenian_tax).max(Decimal::ZERO);
+            let due = slovenian_tax - credit;
+
+            totals.gross += gross;
+            totals.foreign_tax += foreign_tax;
+            totals.slovenian_tax += slovenian_tax;
+            totals.credit += credit;
+            totals.due += due;
+
+            DividendTax {
+                date: dividend.date.format("%Y-%m-%d").to_string(),
+                payer: dividend.name.clone(),
+                payer_id: dividend.payer_id.clone(),
+                source_country: dividend.source_country.clone(),
+                gross,
+                foreign_tax,
+                slovenian_tax,
+                credit,
+                due,
+                relief,
+            }
+        })
+        .collect();
+
+    TaxCalculation {
+        year,
+        rate: DIVIDEND_TAX_RATE,
+        dividends,
+        totals,
+    }
+}
+
+impl TaxCalculation {
+    pub fn print_summary(&self) {
+        println!(
+            "Estimated dividend tax for {} at {}%",
+            self.year,
+            (self.rate * Decimal::ONE_HUNDRED).normalize()
+        );
+        println!();
+        println!(
+            "{:<10}  {:<30}  {:>2}  {:>10}  {:>10}  {:>10}  {:>10}",
+            "Date", "Payer", "", "Gross", "SI tax", "Credit", "Due"
+        );
+        for dividend in &self.dividends {
+            println!(
+                "{:<10}  {:<30.30}  {:>2}  {:>10.2}  {:>10.2}  {:>10.2}  {:>10.2}{}",
+                dividend.date,
+                dividend.payer,
+                dividend.source_country,
+                dividend.gross,
+                dividend.slovenian_tax,
+                dividend.credit,
+                dividend.due,
+                if dividend.relief { "  relief" } else { "" }
+            );
+        }
+        println!();
+        println!("Gross dividends:     {:>10.2} EUR", self.totals.gross);
+        println!("Foreign tax claimed: {:>10.2} EUR", self.totals.foreign_tax);
+        println!(
+            "Slovenian tax:       {:>10.2} EUR",
+            self.totals.slovenian_tax
+        );
+        println!("Foreign tax credit:  {:>10.2} EUR", self.totals.credit);
+        println!("Expected payment:    {:>10.2} EUR", self.totals.due);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{money::Money, withholding::Withholding};
+
+    fn dividend(gross: &str, tax: &str) -> Dividend {
+        Dividend {
+            date: NaiveDate::from_ymd_opt(2023, 5, 18).unwrap(),
+            payer_tax_number: None,
+            payer_id: "US0378331005".to_owned(),
+            name: "Apple".to_owned(),
+            address: "One Apple Park Way, Cupertino, CA 95014".to_owned(),
+            payer_country: "US".to_owned(),
+            source_country: "US".to_owned(),
+            amount: Money::eur(gross.parse().unwrap()),
+            tax: Money::eur(tax.parse().unwrap()),
+            withheld: Money::eur(tax.parse().unwrap()),
+            withholding: Withholding::Reported,
+            relief: None,
+        }
+    }
+
+    fn eur(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn credits_foreign_tax_against_slovenian_tax() {
+        let calculation = calculate(2023, &[dividend("100.00", "15.00")]);
+        let tax = &calculation.dividends[0];
+        assert_eq!(tax.slovenian_tax, eur("25.00"));
+        assert_eq!(tax.credit, eur("15.00"));
+        assert_eq!(tax.due, eur("10.00"));
+    }
+
+    #[test]
+    fn limits_credit_to_slovenian_tax() {
+        let calculation = calculate(2023, &[dividend("100.00", "35.00")]);
+        let tax = &calculation.dividends[0];
+        assert_eq!(tax.credit, eur("25.00"));
+        assert_eq!(tax.due, Decimal::ZERO);
+    }
+
+    #[test]
+    fn exempts_dividends_claiming_treaty_relief() {
+        let mut exempt = dividend("100.00", "15.00");
+        exempt.relief = Some("Exempt under Article 10".to_owned());
+        let calculation = calculate(2023, &[exempt, dividend("100.00", "15.00")]);
+        let tax = &calculation.dividends[0];
+        assert!(tax.relief);
+        assert_eq!(tax.slovenian_tax, Decimal::ZERO);
+        assert_eq!(tax.credit, Decimal::ZERO);
+        assert_eq!(tax.due, Decimal::ZERO);
+        assert_eq!(calculation.totals.gross, eur("200.00"));
+        assert_eq!(calculation.totals.foreign_tax, eur("30.00"));
+        assert_eq!(calculation.totals.slovenian_tax, eur("25.00"));
+        assert_eq!(calculation.totals.due, eur("10.00"));
+    }
+
+    #[test]
+    fn rounds_each_dividend_to_cents() {
+        // 0.25 * 0.10 = 0.025 rounds half away from zero.
+        let calculation = calculate(
+            2023,
+            &[
+                dividend("0.10", "0"),
+                dividend("0.10", "0"),
+                dividend("1.005", "0.1"),
+            ],
+        );
+        assert_eq!(calculation.dividends[0].slovenian_tax, eur("0.03"));
+        assert_eq!(calculation.dividends[2].gross, eur("1.01"));
+        assert_eq!(calculation.dividends[2].slovenian_tax, eur("0.25"));
+        assert_eq!(calculation.totals.gross, eur("1.21"));
+        assert_eq!(calculation.totals.slovenian_tax, eur("0.31"));
+        assert_eq!(calculation.totals.credit, eur("0.10"));
+        assert_eq!(calculation.totals.due, eur("0.21"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use rust_decimal::Decimal;
+
+use crate::{
+    output::OutputFormat,
+    rates::DEFAULT_MAX_LOOKBACK_DAYS,
+    summary::{Grouping, ReportFormat},
+};
+
+/// Prepares FURS dividend returns (Doh-Div) from broker exports.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Writes the Doh-Div return for the given broker exports
+    Div(DivArgs),
+    /// Looks up Banka Slovenije reference rates
+    Rates(RatesArgs),
+    /// Inspects the payer registry
+    Registry(RegistryArgs),
+    /// Summarizes the dividends of the given broker exports by payer, country and month, or
+    /// lists them with --rows
+    Report(ReportArgs),
+    /// Estimates the Slovenian tax and foreign tax credit for the given broker exports
+    Tax(TaxArgs),
+}
+
+#[derive(Args)]
+pub struct InputArgs {
+    /// Broker exports, the broker is recognized from the header row
+    #[arg(value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+    /// Revolut account statement CSV, may be repeated
+    #[arg(long, value_name = "FILE")]
+    pub revolut: Vec<PathBuf>,
+    /// Trading 212 history export CSV, may be repeated
+    #[arg(long, value_name = "FILE")]
+    pub t212: Vec<PathBuf>,
+    /// Interactive Brokers Flex Query XML with cash transactions, may be repeated
+    #[arg(long, value_name = "FILE")]
+    pub ibkr: Vec<PathBuf>,
+    /// DEGIRO account statement CSV (Account.csv), may be repeated
+    #[arg(long, value_name = "FILE")]
+    pub degiro: Vec<PathBuf>,
+    #[command(flatten)]
+    pub rates: RateArgs,
+    #[command(flatten)]
+    pub registry: RegistryFileArgs,
+    /// JSON file overriding the built-in withholding and double taxation treaty rates
+    #[arg(long, value_name = "FILE")]
+    pub treaties: Option<PathBuf>,
+    /// Ask for the address and country of payers missing from the registry and save them
+    #[arg(long, short)]
+    pub interactive: bool,
+}
+
+#[derive(Args)]
+pub struct RegistryFileArgs {
+    /// Security registry with payer addresses and countries
+    #[arg(
+        long = "registry",
+        value_name = "FILE",
+        default_value = "securities.json"
+    )]
+    pub registry_file: PathBuf,
+}
+
+#[derive(Args)]
+pub struct RateArgs {
+    /// Banka Slovenije reference rate list
+    #[arg(long = "rates", value_name = "FILE", default_value = "rates.xml")]
+    pub rates_file: PathBuf,
+    /// How many days to look back for a rate when none was published on the receipt date
+    #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_MAX_LOOKBACK_DAYS)]
+    pub max_rate_lookback: u32,
+}
+
+#[derive(Args)]
+pub struct DivArgs {
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// Your Slovenian tax number, asked for on stdin when omitted
+    #[arg(long)]
+    pub tax_id: Option<String>,
+    /// Only include dividends received in this year (Europe/Ljubljana time), required when
+    /// they span several years unless --split-by-year is given
+    #[arg(long, conflicts_with = "split_by_year")]
+    pub year: Option<i32>,
+    /// Write one return per year, adding the year to the output file name
+    #[arg(long)]
+    pub split_by_year: bool,
+    /// Output file, defaults to result.csv or result.xml
+    #[arg(long, short)]
+    pub out: Option<PathBuf>,
+    /// Output format, inferred from the output file extension when omitted
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Do not write the return if any row of the exports could not be imported
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(Args)]
+pub struct RatesArgs {
+    #[command(flatten)]
+    pub rates: RateArgs,
+    /// Currency code, e.g. USD or GBX
+    #[arg(long)]
+    pub currency: String,
+    /// Receipt date in YYYY-MM-DD format
+    #[arg(long)]
+    pub date: NaiveDate,
+    /// Amount in the given currency to convert into EUR
+    #[arg(long)]
+    pub amount: Option<Decimal>,
+    /// Amount in EUR to convert into the given currency
+    #[arg(long)]
+    pub eur_amount: Option<Decimal>,
+}
+
+#[derive(Args)]
+pub struct RegistryArgs {
+    #[command(flatten)]
+    pub registry: RegistryFileArgs,
+    #[command(subcommand)]
+    pub command: RegistryCommand,
+}
+
+#[derive(Subcommand)]
+pub enum RegistryCommand {
+    /// Lists all known payers
+    List,
+    /// Shows the registry entries for an ISIN or ticker
+    Show { id: String },
+    /// Converts places.json and revolut.json into the registry file
+    Migrate {
+        /// Ticker to address and country map
+        #[arg(long, value_name = "FILE", default_value = "places.json")]
+        places: PathBuf,
+        /// Revolut ticker to ISIN and name map
+        #[arg(long, value_name = "FILE", default_value = "revolut.json")]
+        revolut_info: PathBuf,
+        /// Overwrite an existing registry file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Adds ticker, ISIN and name mappings found in broker exports to the registry
+    Learn {
+        /// Trading 212 history export CSV, may be repeated
+        #[arg(long, value_name = "FILE", required = true)]
+        t212: Vec<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+pub struct ReportArgs {
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// Only include dividends received in this year (Europe/Ljubljana time)
+    #[arg(long)]
+    pub year: Option<i32>,
+    /// Group by payer, source country or month, may be repeated; defaults to all three
+    #[arg(long, value_enum)]
+    pub by: Vec<Grouping>,
+    /// Summary format
+    #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+    pub format: ReportFormat,
+    /// List the individual dividends instead of summarizing them
+    #[arg(long, conflicts_with_all = ["by", "format"])]
+    pub rows: bool,
+    /// Write the summary to a file instead of stdout
+    #[arg(long, short)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct TaxArgs {
+    #[command(flatten)]
+    pub inputs: InputArgs,
+    /// Tax year, required when the dividends span several years
+    #[arg(long)]
+    pub year: Option<i32>,
+    /// Print the calculation as JSON
+    #[arg(long)]
+    pub json: bool,
+}
@@ -0,0 +1,66 @@
+/// A currency quoted in a fraction of its major unit, e.g. pence instead of pounds.
+pub struct MinorUnit {
+    pub major: &'static str,
+    pub per_major: u32,
+}
+
+/// Minor-unit codes used by exchanges and brokers. Codes are case sensitive, `GBp` is pence while
+/// `GBP` is pounds.
+const MINOR_UNITS: &[(&str, MinorUnit)] = &[
+    (
+        "GBX",
+        MinorUnit {
+            major: "GBP",
+            per_major: 100,
+        },
+    ),
+    (
+        "GBp",
+        MinorUnit {
+            major: "GBP",
+            per_major: 100,
+        },
+    ),
+    (
+        "ZAc",
+        MinorUnit {
+            major: "ZAR",
+            per_major: 100,
+        },
+    ),
+    (
+        "ZAC",
+        MinorUnit {
+            major: "ZAR",
+            per_major: 100,
+        },
+    ),
+    (
+        "ILA",
+        MinorUnit {
+            major: "ILS",
+            per_major: 100,
+        },
+    ),
+    (
+        "ILa",
+        MinorUnit {
+            major: "ILS",
+            per_major: 100,
+        },
+    ),
+    (
+        "USX",
+        MinorUnit {
+            major: "USD",
+            per_major: 100,
+        },
+    ),
+];
+
+pub fn minor_unit(currency: &str) -> Option<&'static MinorUnit> {
+    MINOR_UNITS
+        .iter()
+        .find(|(code, _)| *code == currency)
+        .map(|(_, unit)| unit)
+}
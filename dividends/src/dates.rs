@@ -0,0 +1,57 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Europe::Ljubljana;
+
+/// Parses a broker timestamp into the receipt date in Slovenian local time. Timestamps without
+/// an offset are treated as UTC, which is what both Revolut and Trading 212 export.
+pub fn parse_receipt_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Ljubljana).date_naive());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(timestamp.and_utc().with_timezone(&Ljubljana).date_naive());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn converts_utc_timestamps_to_ljubljana_dates() {
+        assert_eq!(parse_receipt_date("2023-12-31 23:30:00"), date(2024, 1, 1));
+        assert_eq!(parse_receipt_date("2023-12-31T23:30:00Z"), date(2024, 1, 1));
+        assert_eq!(
+            parse_receipt_date("2023-12-31T22:30:00.123Z"),
+            date(2023, 12, 31)
+        );
+        // Summer time is two hours ahead of UTC.
+        assert_eq!(parse_receipt_date("2023-07-31 22:30:00"), date(2023, 8, 1));
+        assert_eq!(parse_receipt_date("2023-07-31 21:30:00"), date(2023, 7, 31));
+    }
+
+    #[test]
+    fn keeps_explicit_offsets() {
+        assert_eq!(
+            parse_receipt_date("2023-12-31T23:30:00+01:00"),
+            date(2023, 12, 31)
+        );
+        assert_eq!(
+            parse_receipt_date("2023-12-31T20:30:00-05:00"),
+            date(2024, 1, 1)
+        );
+    }
+
+    #[test]
+    fn reads_plain_dates() {
+        assert_eq!(parse_receipt_date(" 2023-12-31 "), date(2023, 12, 31));
+        assert_eq!(parse_receipt_date("31.12.2023"), None);
+    }
+}
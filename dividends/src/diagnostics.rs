@@ -0,0 +1,121 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chrono::NaiveDate;
+use thiserror::Error;
+
+use crate::Broker;
+
+/// Why a row of a broker export could not be turned into a dividend.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("missing column {field}")]
+    MissingColumn { field: &'static str },
+    #[error("missing value for {field}")]
+    MissingField { field: &'static str },
+    #[error("invalid {field} {value:?}")]
+    InvalidField { field: &'static str, value: String },
+    #[error("no {currency} exchange rate for {date}")]
+    MissingRate { currency: String, date: NaiveDate },
+    #[error("no payer address for {0}")]
+    UnknownPayer(PayerInfo),
+    #[error("no ISIN and name for {0}")]
+    UnknownSecurity(PayerInfo),
+    #[error("reversal or withholding without a matching dividend: {0}")]
+    Unmatched(String),
+    #[error("unrecognized action {0:?}")]
+    UnknownAction(String),
+}
+
+/// What a broker export tells about a payer missing from the registry.
+#[derive(Clone, Debug)]
+pub struct PayerInfo {
+    pub ticker: String,
+    pub isin: Option<String>,
+    pub name: Option<String>,
+    pub currency: Option<String>,
+}
+
+impl fmt::Display for PayerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ticker)?;
+        if let Some(isin) = &self.isin {
+            write!(f, ", {isin}")?;
+        }
+        if let Some(name) = &self.name {
+            write!(f, ", {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A dropped row, with enough context to find it in the export.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub source: PathBuf,
+    pub line: u64,
+    pub broker: Broker,
+    pub error: ImportError,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{} ({}): {}",
+            self.source.display(),
+            self.line,
+            self.broker,
+            self.error
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn push(&mut self, source: &Path, line: u64, broker: Broker, error: ImportError) {
+        let diagnostic = Diagnostic {
+            source: source.to_owned(),
+            line,
+            broker,
+            error,
+        };
+        log::debug!("{diagnostic}");
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Success when no rows were dropped, 2 otherwise.
+    pub fn exit_code(&self) -> ExitCode {
+        if self.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(2)
+        }
+    }
+
+    /// Prints every dropped row to stderr.
+    pub fn print_summary(&self) {
+        if self.is_empty() {
+            return;
+        }
+        eprintln!("{} rows were dropped:", self.len());
+        for diagnostic in &self.diagnostics {
+            eprintln!("  {diagnostic}");
+        }
+    }
+}
@@ -0,0 +1,532 @@
+mod degiro;
+mod ibkr;
+mod revolut;
+mod trading212;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    hash::Hash,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use csv::{Reader, StringRecord};
+use rust_decimal::Decimal;
+
+use crate::{
+    diagnostics::{Diagnostics, ImportError, PayerInfo},
+    interactive::prompt_for_payer,
+    money::Money,
+    rates::RateTable,
+    registry::{Registry, Security},
+    treaty::{IncomeType, TreatyTable},
+    withholding::{gross_up, Withholding},
+    Broker, Dividend,
+};
+
+pub use trading212::listings as trading212_listings;
+
+/// A dividend as a broker reports it, before it is matched against the registry and converted
+/// to EUR.
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub ticker: String,
+    pub isin: Option<String>,
+    pub name: Option<String>,
+    /// Currency the security is traded in, shown when asking about an unknown payer.
+    pub currency: Option<String>,
+    /// Country of the issuer, used when the registry has none.
+    pub country: Option<String>,
+    /// The amount credited to the account, after withholding.
+    pub net: Money,
+    /// The withheld tax, when the broker reports it.
+    pub withheld: Option<Money>,
+}
+
+/// A transaction read from an export, or why the row could not be read.
+pub struct Entry {
+    pub line: u64,
+    pub transaction: Result<Transaction, ImportError>,
+}
+
+/// Reads the dividends out of one broker's exports.
+pub trait BrokerImporter {
+    fn broker(&self) -> Broker;
+
+    /// Whether a file whose first line is `header` is an export of this broker.
+    fn detect(&self, header: &str) -> bool;
+
+    /// Reads the dividend transactions of an export. Rows that are not dividends are skipped,
+    /// rows that look like dividends but cannot be read are returned as errors.
+    fn read(&self, path: &Path) -> Result<Vec<Entry>>;
+}
+
+const IMPORTERS: &[&dyn BrokerImporter] = &[
+    &revolut::Revolut,
+    &trading212::Trading212,
+    &ibkr::InteractiveBrokers,
+    &degiro::Degiro,
+];
+
+pub fn importer(broker: Broker) -> &'static dyn BrokerImporter {
+    IMPORTERS
+        .iter()
+        .copied()
+        .find(|importer| importer.broker() == broker)
+        .expect("every broker has an importer")
+}
+
+/// Picks the importer for an export by looking at its header row.
+pub fn detect(path: &Path) -> Result<&'static dyn BrokerImporter> {
+    let file = File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    // XML exports are recognized by their root element, which follows the declaration.
+    if header.trim_start_matches('\u{feff}').starts_with("<?xml") {
+        reader.read_line(&mut header)?;
+    }
+    let header = header.trim_start_matches('\u{feff}').trim_end();
+    match IMPORTERS.iter().find(|importer| importer.detect(header)) {
+        Some(importer) => Ok(*importer),
+        None => bail!("Unable to recognize the broker of {}", path.display()),
+    }
+}
+
+/// Everything the importers need to turn export rows into dividends.
+pub struct ImportContext {
+    pub registry: Registry,
+    pub registry_file: PathBuf,
+    pub rates: RateTable,
+    pub treaties: TreatyTable,
+    pub interactive: bool,
+    /// Only dividends received in this year are imported, the others are skipped before they
+    /// are looked up or converted.
+    pub year: Option<i32>,
+    /// Payers the user declined to add, by ISIN or ticker, so they are only asked for once.
+    pub skipped_payers: HashSet<String>,
+}
+
+impl ImportContext {
+    /// Imports every dividend of an export, collecting the rows that could not be imported.
+    pub fn import(
+        &mut self,
+        importer: &dyn BrokerImporter,
+        path: &Path,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Vec<Dividend>> {
+        let broker = importer.broker();
+        let mut dividends = vec![];
+        for entry in importer.read(path)? {
+            let transaction = match entry.transaction {
+                Ok(transaction) => transaction,
+                Err(error) => {
+                    diagnostics.push(path, entry.line, broker, error);
+                    continue;
+                }
+            };
+            if let Some(year) = self.year.filter(|year| transaction.date.year() != *year) {
+                log::warn!(
+                    "Skipping dividend from {} received on {}, outside of {year}",
+                    transaction.name.as_deref().unwrap_or(&transaction.ticker),
+                    transaction.date
+                );
+                continue;
+            }
+            let mut result = self.to_dividend(broker, &transaction);
+            if let Err(error) = &result {
+                if self.resolve_unknown_payer(broker, error)? {
+                    result = self.to_dividend(broker, &transaction);
+                }
+            }
+            match result {
+                Ok(dividend) => dividends.push(dividend),
+                Err(error) => diagnostics.push(path, entry.line, broker, error),
+            }
+        }
+        Ok(dividends)
+    }
+
+    /// Asks for a payer missing from the registry when running interactively, saving the answer.
+    /// Returns whether the row should be imported again.
+    fn resolve_unknown_payer(&mut self, broker: Broker, error: &ImportError) -> Result<bool> {
+        let (ImportError::UnknownPayer(payer) | ImportError::UnknownSecurity(payer)) = error else {
+            return Ok(false);
+        };
+        let key = payer.isin.as_ref().unwrap_or(&payer.ticker);
+        if !self.interactive || self.skipped_payers.contains(key) {
+            return Ok(false);
+        }
+        if !prompt_for_payer(&mut self.registry, broker, payer)? {
+            self.skipped_payers.insert(key.clone());
+            return Ok(false);
+        }
+        self.registry.save(&self.registry_file)?;
+        Ok(true)
+    }
+
+    /// Completes a transaction with the payer data from the registry and converts it to EUR,
+    /// estimating the withheld tax when the broker only reports the net amount.
+    fn to_dividend(
+        &self,
+        broker: Broker,
+        transaction: &Transaction,
+    ) -> Result<Dividend, ImportError> {
+        let payer = || PayerInfo {
+            ticker: transaction.ticker.clone(),
+            isin: transaction.isin.clone(),
+            name: transaction.name.clone(),
+            currency: transaction.currency.clone(),
+        };
+        // Without an ISIN and name in the export both have to come from the registry.
+        let unknown = || match (&transaction.isin, &transaction.name) {
+            (Some(_), Some(_)) => ImportError::UnknownPayer(payer()),
+            _ => ImportError::UnknownSecurity(payer()),
+        };
+
+        let security = self
+            .registry
+            .resolve(transaction.isin.as_deref(), &transaction.ticker, broker)
+            .ok_or_else(unknown)?;
+        let (Some(isin), Some(name)) = (
+            transaction.isin.as_deref().or(security.isin.as_deref()),
+            transaction.name.as_deref().or(security.name()),
+        ) else {
+            return Err(ImportError::UnknownSecurity(payer()));
+        };
+        // Entries migrated without an ISIN are matched by ticker, check them against the
+        // ISIN from the export instead.
+        if security.isin.is_none() {
+            security.check_country(isin, name);
+        }
+        let country = security
+            .export_isin_country(isin)
+            .or(transaction.country.as_deref());
+        let (address, payer_country) =
+            payer_address(security, country).ok_or_else(|| ImportError::UnknownPayer(payer()))?;
+        let source_country = source_country(security, &payer_country);
+
+        let date = transaction.date;
+        let net = convert_value(date, transaction.net.clone(), &self.rates)?;
+        let (amount, withheld, withholding) = match &transaction.withheld {
+            Some(withheld) => {
+                let withheld = convert_value(date, withheld.clone(), &self.rates)?;
+                (net + withheld.clone(), withheld, Withholding::Reported)
+            }
+            None => {
+                let income = IncomeType::of(security.security_type);
+                match self.treaties.withholding_rate(&source_country, income) {
+                    Some(rate) => {
+                        let (gross, withheld) = gross_up(&net, rate);
+                        (gross, withheld, Withholding::Estimated(rate))
+                    }
+                    None => {
+                        log::warn!(
+                            "No withholding rate for {source_country}, reporting the net \
+                             dividend from {name} on {date}"
+                        );
+                        (net, Money::zero("EUR"), Withholding::Unknown)
+                    }
+                }
+            }
+        };
+        let tax = self.claimed_foreign_tax(security, &source_country, date, &amount, &withheld);
+        let relief = self.relief_statement(security, &source_country);
+
+        Ok(Dividend {
+            date,
+            payer_tax_number: security.payer_tax_number.clone(),
+            payer_id: isin.to_owned(),
+            name: name.to_owned(),
+            address,
+            payer_country,
+            source_country,
+            amount,
+            tax,
+            withheld,
+            withholding,
+            relief,
+        })
+    }
+
+    /// Caps the foreign tax claimed at the treaty rate, warning when more was withheld.
+    fn claimed_foreign_tax(
+        &self,
+        security: &Security,
+        source_country: &str,
+        date: NaiveDate,
+        gross: &Money,
+        withheld: &Money,
+    ) -> Money {
+        let income = IncomeType::of(security.security_type);
+        let claimed = self
+            .treaties
+            .creditable(source_country, income, gross, withheld);
+        if claimed != *withheld {
+            let rate = self
+                .treaties
+                .treaty_rate(source_country, income)
+                .unwrap_or_default();
+            log::warn!(
+                "{} EUR was withheld from the {} dividend on {date}, more than the {}% treaty \
+                 rate with {source_country}; claiming {} EUR, the rest has to be reclaimed at \
+                 source",
+                withheld.to_xml_string(),
+                security.name().unwrap_or("-"),
+                (rate * Decimal::ONE_HUNDRED).normalize(),
+                claimed.to_xml_string()
+            );
+            if source_country == "US" {
+                log::warn!(
+                    "US dividends are withheld at 30% without a W-8BEN, check the broker has \
+                     one on file"
+                );
+            }
+        }
+        claimed
+    }
+
+    /// The treaty relief claimed for a dividend, either as stated for the payer in the registry
+    /// or for every payout from the source country as configured in the treaty table.
+    fn relief_statement(&self, security: &Security, source_country: &str) -> Option<String> {
+        let income = IncomeType::of(security.security_type);
+        security
+            .relief
+            .as_deref()
+            .or_else(|| self.treaties.relief(source_country, income))
+            .map(str::to_owned)
+    }
+}
+
+fn payer_address(security: &Security, country: Option<&str>) -> Option<(String, String)> {
+    Some((
+        security.address.clone()?,
+        security.payer_country().or(country)?.to_owned(),
+    ))
+}
+
+fn source_country(security: &Security, payer_country: &str) -> String {
+    security
+        .source_country()
+        .unwrap_or(payer_country)
+        .to_owned()
+}
+
+fn convert_value(date: NaiveDate, money: Money, rates: &RateTable) -> Result<Money, ImportError> {
+    let eur = rates
+        .foreign_to_eur(date, &money)
+        .ok_or_else(|| ImportError::MissingRate {
+            currency: money.to_major_unit().currency,
+            date,
+        })?;
+    Ok(eur.round_cents())
+}
+
+/// Splits a CSV header line into its column names.
+fn csv_columns(header: &str) -> Vec<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(header.as_bytes());
+    match reader.records().next() {
+        Some(Ok(record)) => record.iter().map(str::to_owned).collect(),
+        _ => vec![],
+    }
+}
+
+/// Whether a CSV header line has all of the given columns.
+fn has_columns(header: &str, columns: &[&str]) -> bool {
+    let found = csv_columns(header);
+    columns
+        .iter()
+        .all(|column| found.iter().any(|found| found == column))
+}
+
+/// Reads a CSV export, turning each row into a `T` with `parse` and keeping the line it came
+/// from. Rows `parse` returns `None` for are skipped.
+fn read_rows<T>(
+    path: &Path,
+    parse: impl Fn(&StringRecord, &HashMap<String, usize>) -> Result<Option<T>, ImportError>,
+) -> Result<Vec<(u64, Result<T, ImportError>)>> {
+    let file = File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    let headers = header_indices(&mut reader)?;
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.with_context(|| format!("Unable to read {}", path.display()))?;
+        let line = record.position().map_or(0, |position| position.line());
+        match parse(&record, &headers) {
+            Ok(Some(row)) => rows.push((line, Ok(row))),
+            Ok(None) => {}
+            Err(error) => rows.push((line, Err(error))),
+        }
+    }
+    Ok(rows)
+}
+
+/// Whether a cash row of an export pays out a dividend or withholds tax from one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CashKind {
+    Dividend,
+    Tax,
+}
+
+/// A dividend or tax row of an export that books the withheld tax as a separate cash movement.
+struct CashRow {
+    date: NaiveDate,
+    ticker: String,
+    isin: Option<String>,
+    name: Option<String>,
+    currency: Option<String>,
+    kind: CashKind,
+    /// Credited for dividends, debited (negative) for withheld tax.
+    amount: Money,
+}
+
+/// Groups the dividend and tax rows of an export into payouts by `key` and nets each into a
+/// transaction, so that tax is attached to its dividend and reversals cancel out the rows they
+/// correct. `amount_field` names the amount column in errors.
+fn net_payouts<K: Eq + Hash>(
+    rows: Vec<(u64, Result<CashRow, ImportError>)>,
+    key: impl Fn(&CashRow) -> K,
+    amount_field: &'static str,
+) -> Vec<Entry> {
+    let mut payouts: Vec<Vec<(u64, CashRow)>> = vec![];
+    let mut keys = HashMap::new();
+    let mut entries = vec![];
+    for (line, row) in rows {
+        let row = match row {
+            Ok(row) => row,
+            Err(error) => {
+                entries.push(Entry {
+                    line,
+                    transaction: Err(error),
+                });
+                continue;
+            }
+        };
+        let index = *keys.entry(key(&row)).or_insert_with(|| {
+            payouts.push(vec![]);
+            payouts.len() - 1
+        });
+        payouts[index].push((line, row));
+    }
+
+    entries.extend(
+        payouts
+            .into_iter()
+            .filter_map(|rows| net_payout(rows, amount_field)),
+    );
+    entries.sort_by_key(|entry| entry.line);
+    entries
+}
+
+fn net_payout(rows: Vec<(u64, CashRow)>, amount_field: &'static str) -> Option<Entry> {
+    let (line, first) = rows.first()?;
+    let currency = &first.amount.currency;
+    if let Some((line, other)) = rows
+        .iter()
+        .find(|(_, row)| &row.amount.currency != currency)
+    {
+        return Some(Entry {
+            line: *line,
+            transaction: Err(invalid(amount_field, &other.amount.currency)),
+        });
+    }
+
+    let sum = |kind: CashKind| -> Decimal {
+        rows.iter()
+            .filter(|(_, row)| row.kind == kind)
+            .map(|(_, row)| row.amount.amount)
+            .sum()
+    };
+    let dividend = sum(CashKind::Dividend);
+    let withheld = -sum(CashKind::Tax);
+    let payer = first.name.as_deref().unwrap_or(&first.ticker);
+    if dividend.is_zero() && withheld.is_zero() {
+        log::info!("Skipping reversed dividend of {payer} on {}", first.date);
+        return None;
+    }
+    if dividend <= Decimal::ZERO {
+        return Some(Entry {
+            line: *line,
+            transaction: Err(ImportError::Unmatched(format!("{payer} on {}", first.date))),
+        });
+    }
+
+    // The dividend rather than a tax row or a correction describes the payout.
+    let (line, paid) = rows
+        .iter()
+        .find(|(_, row)| row.kind == CashKind::Dividend && row.amount.amount > Decimal::ZERO)
+        .unwrap_or(&rows[0]);
+    Some(Entry {
+        line: *line,
+        transaction: Ok(Transaction {
+            date: paid.date,
+            ticker: paid.ticker.clone(),
+            isin: paid.isin.clone(),
+            name: paid.name.clone(),
+            currency: paid.currency.clone(),
+            country: None,
+            net: Money::new(dividend - withheld, currency.clone()),
+            withheld: Some(Money::new(withheld, currency.clone())),
+        }),
+    })
+}
+
+fn header_indices(reader: &mut Reader<BufReader<File>>) -> Result<HashMap<String, usize>> {
+    Ok(reader
+        .headers()?
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (v.trim_start_matches('\u{feff}').to_owned(), i))
+        .collect())
+}
+
+fn field<'r>(
+    record: &'r StringRecord,
+    headers: &HashMap<String, usize>,
+    field: &'static str,
+) -> Result<&'r str, ImportError> {
+    let index = headers
+        .get(field)
+        .ok_or(ImportError::MissingColumn { field })?;
+    record
+        .get(*index)
+        .ok_or(ImportError::MissingField { field })
+}
+
+fn optional_field(
+    record: &StringRecord,
+    headers: &HashMap<String, usize>,
+    field: &str,
+) -> Option<String> {
+    let value = record.get(*headers.get(field)?)?;
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+fn invalid(field: &'static str, value: &str) -> ImportError {
+    ImportError::InvalidField {
+        field,
+        value: value.to_owned(),
+    }
+}
+
+/// Reads an export given inline through a temporary file, for the importer tests.
+#[cfg(test)]
+fn read_export(importer: &dyn BrokerImporter, name: &str, contents: &str) -> Vec<Entry> {
+    let path = std::env::temp_dir().join(format!("dividends-{}-{name}", std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    let entries = importer.read(&path);
+    std::fs::remove_file(&path).unwrap();
+    entries.unwrap()
+}
+
+/// An amount in cents, for the importer tests.
+#[cfg(test)]
+fn money(cents: i64, currency: &str) -> Money {
+    Money::new(Decimal::new(cents, 2), currency)
+}
@@ -0,0 +1,197 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rust_decimal::Decimal;
+
+use super::{
+    csv_columns, invalid, net_payouts, read_rows, BrokerImporter, CashKind, CashRow, Entry,
+};
+use crate::{diagnostics::ImportError, money::Money, Broker};
+
+const DATE: &[&str] = &["Date", "Datum"];
+const PRODUCT: &[&str] = &["Product", "Produkt"];
+const ISIN: &[&str] = &["ISIN"];
+const DESCRIPTION: &[&str] = &["Description", "Omschrijving", "Beschreibung"];
+/// The change column holds the currency, the unnamed column after it the amount.
+const CHANGE: &[&str] = &["Change", "Mutatie", "Änderung"];
+
+const DIVIDEND: &[&str] = &["Dividend", "Dividende"];
+const DIVIDEND_TAX: &[&str] = &["Dividend Tax", "Dividendbelasting", "Dividendensteuer"];
+
+/// Column positions of a DEGIRO account statement, which is exported with localized headers.
+struct Columns {
+    date: usize,
+    product: usize,
+    isin: usize,
+    description: usize,
+    currency: usize,
+    amount: usize,
+}
+
+impl Columns {
+    fn find(position: impl Fn(&[&str]) -> Option<usize>) -> Option<Self> {
+        let currency = position(CHANGE)?;
+        Some(Columns {
+            date: position(DATE)?,
+            product: position(PRODUCT)?,
+            isin: position(ISIN)?,
+            description: position(DESCRIPTION)?,
+            currency,
+            amount: currency + 1,
+        })
+    }
+}
+
+/// DEGIRO account statements (Account.csv) in English, Dutch or German.
+pub struct Degiro;
+
+impl BrokerImporter for Degiro {
+    fn broker(&self) -> Broker {
+        Broker::Degiro
+    }
+
+    fn detect(&self, header: &str) -> bool {
+        let headers = csv_columns(header);
+        Columns::find(|names| headers.iter().position(|h| names.contains(&h.as_str()))).is_some()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Entry>> {
+        let rows = read_rows(path, parse_record)?;
+        // Dividends and their withholding tax are separate lines, paired up by product and date.
+        Ok(net_payouts(
+            rows,
+            |row| (row.isin.clone(), row.date),
+            "Change",
+        ))
+    }
+}
+
+fn parse_record(
+    record: &StringRecord,
+    headers: &HashMap<String, usize>,
+) -> Result<Option<CashRow>, ImportError> {
+    let columns = Columns::find(|names| names.iter().find_map(|name| headers.get(*name).copied()))
+        .ok_or(ImportError::MissingColumn {
+            field: "Description",
+        })?;
+    let get = |index: usize, field: &'static str| {
+        record
+            .get(index)
+            .map(str::trim)
+            .ok_or(ImportError::MissingField { field })
+    };
+    let description = get(columns.description, "Description")?;
+    let kind = if DIVIDEND.contains(&description) {
+        CashKind::Dividend
+    } else if DIVIDEND_TAX.contains(&description) {
+        CashKind::Tax
+    } else {
+        return Ok(None);
+    };
+
+    let date = get(columns.date, "Date")?;
+    let date = NaiveDate::parse_from_str(date, "%d-%m-%Y").map_err(|_| invalid("Date", date))?;
+    let isin = get(columns.isin, "ISIN")?;
+    if isin.is_empty() {
+        return Err(ImportError::MissingField { field: "ISIN" });
+    }
+    let currency = get(columns.currency, "Change")?;
+    let amount = get(columns.amount, "Change")?;
+    let amount = parse_localized_decimal(amount).ok_or_else(|| invalid("Change", amount))?;
+
+    Ok(Some(CashRow {
+        date,
+        // DEGIRO has no tickers, the ISIN stands in for one.
+        ticker: isin.to_owned(),
+        isin: Some(isin.to_owned()),
+        name: Some(get(columns.product, "Product")?.to_owned()),
+        currency: Some(currency.to_owned()),
+        kind,
+        amount: Money::new(amount, currency),
+    }))
+}
+
+/// Parses amounts written either as `1,234.56` or `1.234,56`; the separator that comes last is
+/// the decimal one.
+fn parse_localized_decimal(value: &str) -> Option<Decimal> {
+    let value = value.trim().trim_matches('"');
+    let normalized = match (value.rfind('.'), value.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => value.replace('.', "").replace(',', "."),
+        (_, Some(_)) if !value.contains('.') => value.replace(',', "."),
+        _ => value.replace(',', ""),
+    };
+    normalized.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::{money, read_export};
+
+    const DUTCH: &str = "\
+Datum,Tijd,Valutadatum,Product,ISIN,Omschrijving,FX,Mutatie,,Saldo,,Order Id
+15-05-2023,07:41,12-05-2023,APPLE INC.,US0378331005,Dividendbelasting,,USD,\"-0,36\",USD,\"2,04\",
+15-05-2023,07:41,12-05-2023,APPLE INC.,US0378331005,Dividend,,USD,\"2,40\",USD,\"2,40\",
+16-05-2023,09:00,16-05-2023,,,Valuta Debitering,\"1,0870\",USD,\"-2,04\",USD,\"0,00\",
+10-06-2023,07:41,09-06-2023,APPLE INC.,US0378331005,Dividendbelasting,,USD,\"0,36\",USD,\"0,36\",
+";
+
+    const GERMAN: &str = "\
+Datum,Uhrzeit,Valutadatum,Produkt,ISIN,Beschreibung,FX,Änderung,,Saldo,,Order-ID
+15-05-2023,07:41,12-05-2023,APPLE INC.,US0378331005,Dividende,,USD,\"1.002,40\",USD,\"2,40\",
+15-05-2023,07:41,12-05-2023,APPLE INC.,US0378331005,Dividendensteuer,,USD,\"-150,36\",USD,\"2,04\",
+";
+
+    #[test]
+    fn detects_localized_headers() {
+        assert!(Degiro.detect(DUTCH.lines().next().unwrap()));
+        assert!(Degiro.detect(GERMAN.lines().next().unwrap()));
+        assert!(Degiro
+            .detect("Date,Time,Value date,Product,ISIN,Description,FX,Change,,Balance,,Order Id"));
+        assert!(!Degiro.detect("Date,Ticker,Type,Total Amount"));
+    }
+
+    #[test]
+    fn parses_localized_decimals() {
+        let parse = |value| parse_localized_decimal(value).unwrap();
+        assert_eq!(parse("2,40"), Decimal::new(240, 2));
+        assert_eq!(parse("-0,36"), Decimal::new(-36, 2));
+        assert_eq!(parse("1.002,40"), Decimal::new(100240, 2));
+        assert_eq!(parse("1,002.40"), Decimal::new(100240, 2));
+        assert_eq!(parse("0.96"), Decimal::new(96, 2));
+        assert_eq!(parse_localized_decimal("n/a"), None);
+    }
+
+    #[test]
+    fn pairs_dividend_tax_by_product_and_date() {
+        let entries = read_export(&Degiro, "degiro-nl.csv", DUTCH);
+        assert_eq!(entries.len(), 2);
+        // Reported at the dividend line rather than the tax line before it.
+        assert_eq!(entries[0].line, 3);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2023, 5, 15).unwrap()
+        );
+        assert_eq!(transaction.isin.as_deref(), Some("US0378331005"));
+        assert_eq!(transaction.name.as_deref(), Some("APPLE INC."));
+        assert_eq!(transaction.net, money(204, "USD"));
+        assert_eq!(transaction.withheld, Some(money(36, "USD")));
+
+        assert_eq!(entries[1].line, 5);
+        assert!(matches!(
+            entries[1].transaction,
+            Err(ImportError::Unmatched(_))
+        ));
+    }
+
+    #[test]
+    fn reads_german_statements() {
+        let entries = read_export(&Degiro, "degiro-de.csv", GERMAN);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(transaction.net, money(85204, "USD"));
+        assert_eq!(transaction.withheld, Some(money(15036, "USD")));
+    }
+}
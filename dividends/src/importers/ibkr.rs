@@ -0,0 +1,355 @@
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use super::{invalid, BrokerImporter, Entry, Transaction};
+use crate::{diagnostics::ImportError, money::Money, Broker};
+
+#[derive(Deserialize)]
+struct FlexQueryResponse {
+    #[serde(rename = "FlexStatements")]
+    statements: FlexStatements,
+}
+
+#[derive(Deserialize)]
+struct FlexStatements {
+    #[serde(rename = "FlexStatement", default)]
+    statements: Vec<FlexStatement>,
+}
+
+#[derive(Deserialize)]
+struct FlexStatement {
+    #[serde(rename = "SecuritiesInfo", default)]
+    securities: SecuritiesInfo,
+    #[serde(rename = "CashTransactions", default)]
+    cash_transactions: CashTransactions,
+}
+
+#[derive(Default, Deserialize)]
+struct SecuritiesInfo {
+    #[serde(rename = "SecurityInfo", default)]
+    securities: Vec<SecurityInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecurityInfo {
+    #[serde(default)]
+    conid: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    isin: String,
+    #[serde(default)]
+    issuer_country_code: String,
+}
+
+#[derive(Default, Deserialize)]
+struct CashTransactions {
+    #[serde(rename = "CashTransaction", default)]
+    transactions: Vec<CashTransaction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CashTransaction {
+    #[serde(rename = "type")]
+    kind: String,
+    currency: String,
+    #[serde(default)]
+    symbol: String,
+    #[serde(default)]
+    conid: String,
+    #[serde(default)]
+    isin: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    issuer_country_code: String,
+    #[serde(default)]
+    date_time: String,
+    #[serde(default)]
+    settle_date: String,
+    #[serde(default)]
+    report_date: String,
+    amount: String,
+    #[serde(rename = "actionID", default)]
+    action_id: String,
+    #[serde(default)]
+    level_of_detail: String,
+}
+
+impl CashTransaction {
+    fn is_dividend(&self) -> bool {
+        matches!(
+            self.kind.as_str(),
+            "Dividends" | "Payment In Lieu Of Dividends"
+        )
+    }
+
+    fn is_withholding(&self) -> bool {
+        self.kind == "Withholding Tax"
+    }
+
+    fn date(&self) -> Option<NaiveDate> {
+        [&self.date_time, &self.settle_date, &self.report_date]
+            .into_iter()
+            .find_map(|value| parse_flex_date(value))
+    }
+}
+
+/// Interactive Brokers Flex Query XML with the Cash Transactions and Securities Info sections.
+pub struct InteractiveBrokers;
+
+impl BrokerImporter for InteractiveBrokers {
+    fn broker(&self) -> Broker {
+        Broker::InteractiveBrokers
+    }
+
+    fn detect(&self, header: &str) -> bool {
+        header.contains("<FlexQueryResponse")
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Entry>> {
+        let contents =
+            read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))?;
+        let response: FlexQueryResponse = serde_xml_rs::from_str(&contents)
+            .with_context(|| format!("Unable to parse {}", path.display()))?;
+        let mut lines = cash_transaction_lines(&contents).into_iter();
+
+        let mut entries = vec![];
+        for statement in response.statements.statements {
+            let securities: HashMap<_, _> = statement
+                .securities
+                .securities
+                .iter()
+                .map(|security| (security.conid.as_str(), security))
+                .collect();
+            let transactions = statement.cash_transactions.transactions;
+            // Flex queries can list summary rows next to the detail rows they sum up.
+            let has_detail = transactions
+                .iter()
+                .any(|transaction| transaction.level_of_detail == "DETAIL");
+            let transactions: Vec<_> = transactions
+                .iter()
+                .map(|transaction| (lines.next().unwrap_or_default(), transaction))
+                .filter(|(_, transaction)| !has_detail || transaction.level_of_detail != "SUMMARY")
+                .filter(|(_, transaction)| {
+                    transaction.is_dividend() || transaction.is_withholding()
+                })
+                .collect();
+            for payout in group_payouts(&transactions) {
+                let transaction = match payout.to_transaction(&securities) {
+                    Ok(Some(transaction)) => Ok(transaction),
+                    Ok(None) => continue,
+                    Err(error) => Err(error),
+                };
+                entries.push(Entry {
+                    line: payout.line,
+                    transaction,
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// The cash transactions of a single dividend payment: the dividend, its withholding tax and
+/// any reversals or corrections of either.
+struct Payout<'t> {
+    line: u64,
+    transactions: Vec<&'t CashTransaction>,
+}
+
+/// Groups cash transactions by IBKR's corporate action id, or by security and date for
+/// statements without one, so that reversals cancel out the entries they correct.
+fn group_payouts<'t>(transactions: &[(u64, &'t CashTransaction)]) -> Vec<Payout<'t>> {
+    let mut payouts: Vec<Payout> = vec![];
+    let mut keys = HashMap::new();
+    for &(line, transaction) in transactions {
+        let key = if transaction.action_id.is_empty() {
+            (
+                transaction.conid.clone(),
+                transaction.date().map(|date| date.to_string()),
+            )
+        } else {
+            (String::new(), Some(transaction.action_id.clone()))
+        };
+        let index = *keys.entry(key).or_insert_with(|| {
+            payouts.push(Payout {
+                line,
+                transactions: vec![],
+            });
+            payouts.len() - 1
+        });
+        payouts[index].transactions.push(transaction);
+    }
+    payouts
+}
+
+impl Payout<'_> {
+    fn to_transaction(
+        &self,
+        securities: &HashMap<&str, &SecurityInfo>,
+    ) -> Result<Option<Transaction>, ImportError> {
+        let mut dividends = Decimal::ZERO;
+        let mut withholding = Decimal::ZERO;
+        for transaction in &self.transactions {
+            let amount: Decimal = transaction
+                .amount
+                .parse()
+                .map_err(|_| invalid("amount", &transaction.amount))?;
+            if transaction.is_dividend() {
+                dividends += amount;
+            } else {
+                withholding += amount;
+            }
+        }
+
+        let first = self.transactions[0];
+        // The dividend date, not the one of a later correction.
+        let paid = self
+            .transactions
+            .iter()
+            .find(|transaction| transaction.is_dividend() && !transaction.amount.starts_with('-'))
+            .unwrap_or(&first);
+        if dividends.is_zero() && withholding.is_zero() {
+            log::info!("Skipping reversed payout {}", first.description);
+            return Ok(None);
+        }
+        if dividends <= Decimal::ZERO {
+            return Err(ImportError::Unmatched(first.description.clone()));
+        }
+
+        let date = paid
+            .date()
+            .ok_or_else(|| invalid("dateTime", &paid.date_time))?;
+        let security = securities.get(first.conid.as_str());
+        let isin = non_empty(&first.isin).or_else(|| security.and_then(|s| non_empty(&s.isin)));
+        let country = non_empty(&first.issuer_country_code)
+            .or_else(|| security.and_then(|s| non_empty(&s.issuer_country_code)));
+        let currency = first.currency.clone();
+        // Withholding is booked as a negative amount, refunds of it as positive ones.
+        let withheld = -withholding;
+
+        Ok(Some(Transaction {
+            date,
+            ticker: first.symbol.clone(),
+            isin,
+            name: security.and_then(|s| non_empty(&s.description)),
+            currency: Some(currency.clone()),
+            country,
+            net: Money::new(dividends - withheld, currency.clone()),
+            withheld: Some(Money::new(withheld, currency)),
+        }))
+    }
+}
+
+/// The line of every `CashTransaction` element in a Flex Query, in document order.
+fn cash_transaction_lines(contents: &str) -> Vec<u64> {
+    const TAG: &str = "<CashTransaction";
+    let mut lines = vec![];
+    let mut line = 1;
+    let mut counted = 0;
+    for (index, _) in contents.match_indices(TAG) {
+        // Skips the enclosing CashTransactions element.
+        let next = contents[index + TAG.len()..].chars().next();
+        if !next.is_some_and(|next| next.is_whitespace() || next == '/' || next == '>') {
+            continue;
+        }
+        line += contents[counted..index].matches('\n').count() as u64;
+        counted = index;
+        lines.push(line);
+    }
+    lines
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+/// Parses the date part of Flex timestamps such as `20230518;202000` or `2023-05-18, 20:20:00`.
+///
+/// Unlike the Revolut and Trading 212 timestamps, these are not converted to Slovenian time with
+/// `parse_receipt_date`. The time is in the account's own time zone, which the export does not
+/// state, and on dividends it is the time IBKR books the day's cash transactions (around
+/// 20:20 New York time) rather than when the payout arrived, so converting it would move every
+/// dividend to the day after its pay date.
+fn parse_flex_date(value: &str) -> Option<NaiveDate> {
+    let date = value.split([';', ',', ' ']).next()?;
+    ["%Y%m%d", "%Y-%m-%d"]
+        .into_iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::{money, read_export};
+
+    const STATEMENT: &str = r#"<FlexQueryResponse queryName="dividends" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567">
+<SecuritiesInfo>
+<SecurityInfo symbol="AAPL" description="APPLE INC" conid="265598" isin="US0378331005" issuerCountryCode="US" />
+<SecurityInfo symbol="KO" description="COCA-COLA CO/THE" conid="8894" isin="US1912161007" issuerCountryCode="US" />
+</SecuritiesInfo>
+<CashTransactions>
+<CashTransaction currency="USD" symbol="AAPL" conid="265598" description="AAPL DIVIDEND" dateTime="20230518;202000" amount="2.4" type="Dividends" actionID="111" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="AAPL" conid="265598" description="AAPL US TAX" dateTime="20230518;202000" amount="-0.36" type="Withholding Tax" actionID="111" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="AAPL" conid="265598" description="AAPL DIVIDEND" dateTime="20230518" amount="2.4" type="Dividends" levelOfDetail="SUMMARY" />
+<CashTransaction currency="USD" symbol="KO" conid="8894" description="KO DIVIDEND" dateTime="20230601" amount="4.6" type="Dividends" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="KO" conid="8894" description="KO US TAX" dateTime="20230601" amount="-1.38" type="Withholding Tax" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="KO" conid="8894" description="KO DIVIDEND REVERSAL" dateTime="20230601" amount="-4.6" type="Dividends" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="KO" conid="8894" description="KO US TAX REVERSAL" dateTime="20230601" amount="1.38" type="Withholding Tax" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="" conid="" description="USD DEBIT INT" dateTime="20230805" amount="-1.00" type="Broker Interest Paid" levelOfDetail="DETAIL" />
+<CashTransaction currency="USD" symbol="KO" conid="8894" description="KO 2022 TAX ADJUSTMENT" dateTime="20230105" amount="0.50" type="Withholding Tax" actionID="999" levelOfDetail="DETAIL" />
+</CashTransactions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>
+"#;
+
+    #[test]
+    fn pairs_withholding_by_action_id() {
+        let entries = read_export(&InteractiveBrokers, "ibkr.xml", STATEMENT);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(entries[0].line, 9);
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2023, 5, 18).unwrap()
+        );
+        assert_eq!(transaction.ticker, "AAPL");
+        assert_eq!(transaction.isin.as_deref(), Some("US0378331005"));
+        assert_eq!(transaction.name.as_deref(), Some("APPLE INC"));
+        assert_eq!(transaction.country.as_deref(), Some("US"));
+        assert_eq!(transaction.net, money(204, "USD"));
+        assert_eq!(transaction.withheld, Some(money(36, "USD")));
+    }
+
+    #[test]
+    fn skips_reversals_and_reports_unmatched_withholding() {
+        let entries = read_export(&InteractiveBrokers, "ibkr-reversal.xml", STATEMENT);
+        // The summary row and the reversed KO dividend are left out.
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].line, 17);
+        assert!(matches!(
+            &entries[1].transaction,
+            Err(ImportError::Unmatched(description)) if description == "KO 2022 TAX ADJUSTMENT"
+        ));
+    }
+
+    #[test]
+    fn parses_flex_dates() {
+        let date = NaiveDate::from_ymd_opt(2023, 5, 18);
+        assert_eq!(parse_flex_date("20230518;202000"), date);
+        assert_eq!(parse_flex_date("2023-05-18, 20:20:00"), date);
+        assert_eq!(parse_flex_date("20230518"), date);
+        // Kept on the pay date even though it is past midnight in Ljubljana.
+        assert_eq!(parse_flex_date("20230518;235900"), date);
+        assert_eq!(parse_flex_date(""), None);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use csv::StringRecord;
+
+use super::{
+    field, has_columns, invalid, net_payouts, optional_field, read_rows, BrokerImporter, CashKind,
+    CashRow, Entry,
+};
+use crate::{dates::parse_receipt_date, diagnostics::ImportError, money::Money, Broker};
+
+/// Revolut account statements. They only carry tickers. The old layout has the net amount in
+/// USD, the new one amounts in the currency of the Currency column and the withheld tax on
+/// separate rows. The FX Rate column is Revolut's own rate and is ignored, the return has to use
+/// the reference rates.
+pub struct Revolut;
+
+impl BrokerImporter for Revolut {
+    fn broker(&self) -> Broker {
+        Broker::Revolut
+    }
+
+    fn detect(&self, header: &str) -> bool {
+        has_columns(header, &["Date", "Ticker", "Type", "Total Amount"])
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Entry>> {
+        let rows = read_rows(path, parse_record)?;
+        // Old statements never list the withheld tax, it has to be estimated from the net amount.
+        let lists_tax = rows
+            .iter()
+            .any(|(_, row)| matches!(row, Ok(row) if row.kind == CashKind::Tax));
+        let mut entries = net_payouts(rows, |row| (row.ticker.clone(), row.date), "Total Amount");
+        if !lists_tax {
+            for entry in &mut entries {
+                if let Ok(transaction) = &mut entry.transaction {
+                    transaction.withheld = None;
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn parse_record(
+    record: &StringRecord,
+    headers: &HashMap<String, usize>,
+) -> Result<Option<CashRow>, ImportError> {
+    // The old layout spells types with spaces, the new one with underscores.
+    let kind = field(record, headers, "Type")?.trim().replace('_', " ");
+    let kind = match kind.as_str() {
+        "DIVIDEND" => CashKind::Dividend,
+        kind if kind.starts_with("DIVIDEND TAX") => CashKind::Tax,
+        kind if kind.starts_with("CUSTODY FEE") => {
+            log::info!(
+                "Ignoring a Revolut custody fee, fees do not reduce the dividend on the return"
+            );
+            return Ok(None);
+        }
+        _ => return Ok(None),
+    };
+
+    let date = field(record, headers, "Date")?;
+    let date = parse_receipt_date(date).ok_or_else(|| invalid("Date", date))?;
+    let ticker = field(record, headers, "Ticker")?;
+    let currency = optional_field(record, headers, "Currency");
+    let amount = field(record, headers, "Total Amount")?;
+    let mut amount =
+        parse_amount(amount, currency.as_deref()).ok_or_else(|| invalid("Total Amount", amount))?;
+    // Tax rows are debits, but some statements list them as positive amounts.
+    if kind == CashKind::Tax {
+        amount.amount = -amount.amount.abs();
+    }
+
+    Ok(Some(CashRow {
+        date,
+        ticker: ticker.to_owned(),
+        isin: None,
+        name: None,
+        currency,
+        kind,
+        amount,
+    }))
+}
+
+/// Parses a Total Amount such as `$1.23`, `1.23` or `USD 1.23`. A currency code in the amount
+/// wins over the Currency column, old statements without either are in USD.
+fn parse_amount(value: &str, currency: Option<&str>) -> Option<Money> {
+    let value = value.trim();
+    let (sign, unsigned) = match value.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned.trim_start()),
+        None => ("", value),
+    };
+    let code_length = unsigned
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .count();
+    let (currency, amount) = match code_length {
+        0 => (currency.unwrap_or("USD"), unsigned),
+        3 => unsigned.split_at(3),
+        _ => return None,
+    };
+    Money::parse(&format!("{sign}{amount}"), currency).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::importers::{money, read_export};
+
+    const OLD_LAYOUT: &str = "\
+Date,Ticker,Type,Quantity,Price per share,Total Amount,Currency,FX Rate
+2023-03-10T14:30:12.345Z,AAPL,DIVIDEND,,,$1.23,USD,1.06
+2023-03-11T14:30:12.345Z,AAPL,CASH TOP-UP,,,$100,USD,1.06
+";
+
+    const NEW_LAYOUT: &str = "\
+Date,Ticker,Type,Quantity,Price per share,Total Amount,Currency,FX Rate
+2023-03-10T14:30:12.345Z,AAPL,DIVIDEND,,,USD 2.04,USD,1.06
+2023-03-10T14:30:13.345Z,AAPL,DIVIDEND_TAX,,,USD -0.36,USD,1.06
+2023-03-11T09:00:00.000Z,AAPL,CUSTODY_FEE,,,USD -0.12,USD,1.06
+2023-04-05T10:00:00.000Z,ASML,DIVIDEND,,,EUR 1.45,EUR,1
+2023-04-05T10:00:00.000Z,ASML,DIVIDEND_TAX,,,EUR 0.22,EUR,1
+2023-04-09T14:30:12.345Z,KO,DIVIDEND_TAX,,,USD -0.30,USD,1.08
+";
+
+    #[test]
+    fn reads_net_dividends_of_the_old_layout() {
+        let entries = read_export(&Revolut, "revolut-old.csv", OLD_LAYOUT);
+        assert_eq!(entries.len(), 1);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2023, 3, 10).unwrap()
+        );
+        assert_eq!(transaction.ticker, "AAPL");
+        assert_eq!(transaction.net, money(123, "USD"));
+        // Left to be estimated from the treaty table.
+        assert_eq!(transaction.withheld, None);
+    }
+
+    #[test]
+    fn attaches_tax_rows_of_the_new_layout() {
+        let entries = read_export(&Revolut, "revolut-new.csv", NEW_LAYOUT);
+        assert_eq!(entries.len(), 3);
+
+        let apple = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(apple.net, money(168, "USD"));
+        assert_eq!(apple.withheld, Some(money(36, "USD")));
+
+        // Tax listed as a positive amount is still withheld.
+        let asml = entries[1].transaction.as_ref().unwrap();
+        assert_eq!(asml.net, money(123, "EUR"));
+        assert_eq!(asml.withheld, Some(money(22, "EUR")));
+
+        assert_eq!(entries[2].line, 7);
+        assert!(matches!(
+            entries[2].transaction,
+            Err(ImportError::Unmatched(_))
+        ));
+    }
+
+    #[test]
+    fn parses_amounts_with_currency() {
+        assert_eq!(parse_amount("$1.23", None), Some(money(123, "USD")));
+        assert_eq!(parse_amount("1.23", Some("GBP")), Some(money(123, "GBP")));
+        assert_eq!(
+            parse_amount("EUR 1,234.50", Some("USD")),
+            Some(money(123450, "EUR"))
+        );
+        assert_eq!(parse_amount("-USD 0.36", None), Some(money(-36, "USD")));
+        assert_eq!(parse_amount("US 1.00", None), None);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::BufReader,
+    path::Path,
+};
+
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use rust_decimal::Decimal;
+
+use super::{
+    csv_columns, field, has_columns, header_indices, invalid, optional_field, read_rows,
+    BrokerImporter, Entry, Transaction,
+};
+use crate::{
+    dates::parse_receipt_date, diagnostics::ImportError, isin::validate_isin, money::Money,
+    registry::Listing, Broker,
+};
+
+/// Trading 212 history exports.
+pub struct Trading212;
+
+impl BrokerImporter for Trading212 {
+    fn broker(&self) -> Broker {
+        Broker::Trading212
+    }
+
+    fn detect(&self, header: &str) -> bool {
+        has_columns(header, &["Action", "Time", "ISIN", "Ticker"])
+            && csv_columns(header)
+                .iter()
+                .any(|column| column == "Total" || total_currency(column).is_some())
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Entry>> {
+        Ok(net_adjustments(read_rows(path, parse_record)?))
+    }
+}
+
+/// What a Trading 212 dividend action means for the return.
+enum Action {
+    /// A taxable dividend.
+    Dividend,
+    /// A correction of an earlier dividend of the same security.
+    Adjustment,
+    /// Paid out of capital, it reduces the cost basis instead of being taxed.
+    ReturnOfCapital,
+    /// Property income distributions of UK REITs are other income rather than dividends.
+    PropertyIncome,
+}
+
+impl Action {
+    /// Classifies the `Dividend (...)` actions, `None` for anything else the account lists.
+    fn parse(action: &str) -> Option<Result<Self, ImportError>> {
+        let kind = action.strip_prefix("Dividend")?.trim();
+        let kind = kind
+            .strip_prefix('(')
+            .and_then(|kind| kind.strip_suffix(')'))
+            .unwrap_or(kind)
+            .to_lowercase();
+        Some(match kind.as_str() {
+            "ordinary"
+            | "dividend"
+            | "bonus"
+            | "dividends paid by us corporations"
+            | "dividends paid by foreign corporations" => Ok(Action::Dividend),
+            "return of capital" => Ok(Action::ReturnOfCapital),
+            "property income" => Ok(Action::PropertyIncome),
+            kind if kind.contains("adjustment") => Ok(Action::Adjustment),
+            _ => Err(ImportError::UnknownAction(action.to_owned())),
+        })
+    }
+}
+
+/// A dividend row, or an adjustment to be netted against one.
+struct Row {
+    adjustment: bool,
+    transaction: Transaction,
+}
+
+fn parse_record(
+    record: &StringRecord,
+    headers: &HashMap<String, usize>,
+) -> Result<Option<Row>, ImportError> {
+    let action = field(record, headers, "Action")?;
+    let Some(kind) = Action::parse(action) else {
+        return Ok(None);
+    };
+    let kind = kind?;
+
+    let date = field(record, headers, "Time")?;
+    let date = parse_receipt_date(date).ok_or_else(|| invalid("Time", date))?;
+    let isin = field(record, headers, "ISIN")?;
+    let name = field(record, headers, "Name")?;
+    let ticker = field(record, headers, "Ticker")?;
+    match kind {
+        Action::Dividend | Action::Adjustment => {}
+        Action::ReturnOfCapital => {
+            log::warn!(
+                "Skipping the return of capital from {name} on {date}, it reduces the cost \
+                 basis instead of being taxed as a dividend"
+            );
+            return Ok(None);
+        }
+        Action::PropertyIncome => {
+            log::warn!(
+                "Skipping the property income from {name} on {date}, it has to be reported as \
+                 other income rather than a dividend"
+            );
+            return Ok(None);
+        }
+    }
+
+    // Total is what reached the account, in the account currency; the return wants the amount
+    // before withholding.
+    let (value, currency) = total(record, headers)?;
+    let net = Money::parse(value, currency).map_err(|_| invalid("Total", value))?;
+    let withheld = match optional_field(record, headers, "Withholding tax") {
+        Some(witholding_tax) => {
+            let currency = optional_field(record, headers, "Currency (Withholding tax)")
+                .unwrap_or_else(|| net.currency.clone());
+            Money::parse(&witholding_tax, currency)
+                .map_err(|_| invalid("Withholding tax", &witholding_tax))?
+        }
+        None => Money::zero(net.currency.clone()),
+    };
+
+    Ok(Some(Row {
+        adjustment: matches!(kind, Action::Adjustment),
+        transaction: Transaction {
+            date,
+            ticker: ticker.to_owned(),
+            isin: Some(isin.to_owned()),
+            name: Some(name.to_owned()),
+            currency: optional_field(record, headers, "Currency (Price / share)"),
+            country: None,
+            net,
+            withheld: Some(withheld),
+        },
+    }))
+}
+
+/// Nets each adjustment against the latest dividend of the same ISIN paid on or before it, or
+/// the earliest one when the adjustment comes first. Adjustments without a dividend are imported
+/// on their own when positive and reported otherwise.
+fn net_adjustments(rows: Vec<(u64, Result<Row, ImportError>)>) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut adjustments = vec![];
+    for (line, row) in rows {
+        match row {
+            Ok(row) if row.adjustment => adjustments.push((line, row.transaction)),
+            row => entries.push(Entry {
+                line,
+                transaction: row.map(|row| row.transaction),
+            }),
+        }
+    }
+
+    for (line, adjustment) in adjustments {
+        let dividends = entries
+            .iter_mut()
+            .filter_map(|entry| match &mut entry.transaction {
+                Ok(dividend) if dividend.isin == adjustment.isin => Some(dividend),
+                _ => None,
+            });
+        let (earlier, later): (Vec<_>, Vec<_>) =
+            dividends.partition(|dividend| dividend.date <= adjustment.date);
+        let dividend = earlier
+            .into_iter()
+            .max_by_key(|dividend| dividend.date)
+            .or_else(|| later.into_iter().min_by_key(|dividend| dividend.date));
+        let result = match dividend {
+            Some(dividend) => apply_adjustment(dividend, &adjustment),
+            None if adjustment.net.amount > Decimal::ZERO => {
+                entries.push(Entry {
+                    line,
+                    transaction: Ok(adjustment),
+                });
+                continue;
+            }
+            None => Err(ImportError::Unmatched(format!(
+                "adjustment of {} on {}",
+                adjustment.name.as_deref().unwrap_or(&adjustment.ticker),
+                adjustment.date
+            ))),
+        };
+        if let Err(error) = result {
+            entries.push(Entry {
+                line,
+                transaction: Err(error),
+            });
+        }
+    }
+
+    entries.retain(|entry| match &entry.transaction {
+        Ok(dividend) if dividend.net.amount <= Decimal::ZERO => {
+            log::warn!(
+                "Skipping the dividend from {} on {}, its adjustments reverse it",
+                dividend.name.as_deref().unwrap_or(&dividend.ticker),
+                dividend.date
+            );
+            false
+        }
+        _ => true,
+    });
+    entries.sort_by_key(|entry| entry.line);
+    entries
+}
+
+fn apply_adjustment(
+    dividend: &mut Transaction,
+    adjustment: &Transaction,
+) -> Result<(), ImportError> {
+    if adjustment.net.currency != dividend.net.currency {
+        return Err(invalid("Total", &adjustment.net.currency));
+    }
+    dividend.net.amount += adjustment.net.amount;
+    if let (Some(withheld), Some(correction)) = (&mut dividend.withheld, &adjustment.withheld) {
+        if !correction.amount.is_zero() {
+            if correction.currency != withheld.currency {
+                return Err(invalid("Withholding tax", &correction.currency));
+            }
+            withheld.amount += correction.amount;
+        }
+    }
+    Ok(())
+}
+
+/// The Total column and its currency. Newer exports name the currency in a Currency (Total)
+/// column, older ones in the header, as in `Total (EUR)`.
+fn total<'r>(
+    record: &'r StringRecord,
+    headers: &HashMap<String, usize>,
+) -> Result<(&'r str, String), ImportError> {
+    if headers.contains_key("Total") {
+        let currency =
+            optional_field(record, headers, "Currency (Total)").unwrap_or_else(|| "EUR".to_owned());
+        return Ok((field(record, headers, "Total")?, currency));
+    }
+    let (index, currency) = headers
+        .iter()
+        .find_map(|(column, index)| Some((*index, total_currency(column)?)))
+        .ok_or(ImportError::MissingColumn { field: "Total" })?;
+    let value = record
+        .get(index)
+        .ok_or(ImportError::MissingField { field: "Total" })?;
+    Ok((value, currency.to_owned()))
+}
+
+/// The currency of an old-style `Total (EUR)` column header.
+fn total_currency(column: &str) -> Option<&str> {
+    column
+        .strip_prefix("Total (")?
+        .strip_suffix(')')
+        .filter(|currency| currency.len() == 3)
+}
+
+/// Collects every distinct ticker, ISIN and name combination in a Trading 212 export.
+pub fn listings(trading212: &Path) -> Result<Vec<Listing>> {
+    let file = File::open(trading212)?;
+    let reader = BufReader::new(file);
+    let mut reader = Reader::from_reader(reader);
+    let headers = header_indices(&mut reader)?;
+
+    let mut seen = BTreeSet::new();
+    for record in reader.records() {
+        let record = record?;
+        let (Some(ticker), Some(isin)) = (
+            optional_field(&record, &headers, "Ticker"),
+            optional_field(&record, &headers, "ISIN"),
+        ) else {
+            continue;
+        };
+        if let Err(error) = validate_isin(&isin) {
+            log::warn!("Skipping {ticker}: {error}");
+            continue;
+        }
+        let name = optional_field(&record, &headers, "Name").unwrap_or_default();
+        seen.insert((isin, ticker, name));
+    }
+
+    Ok(seen
+        .into_iter()
+        .map(|(isin, ticker, name)| Listing {
+            broker: Broker::Trading212,
+            ticker,
+            isin,
+            name,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::{money, read_export};
+
+    const HEADER: &str = "Action,Time,ISIN,Ticker,Name,No. of shares,Price / share,\
+Currency (Price / share),Exchange rate,Total,Currency (Total),Withholding tax,\
+Currency (Withholding tax)";
+
+    #[test]
+    fn classifies_dividend_actions() {
+        let parse = |action| Action::parse(action).map(|kind| kind.ok());
+        assert!(matches!(
+            parse("Dividend (Ordinary)"),
+            Some(Some(Action::Dividend))
+        ));
+        assert!(matches!(
+            parse("Dividend (Dividend)"),
+            Some(Some(Action::Dividend))
+        ));
+        assert!(matches!(
+            parse("Dividend (Bonus)"),
+            Some(Some(Action::Dividend))
+        ));
+        assert!(matches!(
+            parse("Dividend (Return of capital)"),
+            Some(Some(Action::ReturnOfCapital))
+        ));
+        assert!(matches!(
+            parse("Dividend (Property income)"),
+            Some(Some(Action::PropertyIncome))
+        ));
+        assert!(matches!(
+            parse("Dividend (Adjustment)"),
+            Some(Some(Action::Adjustment))
+        ));
+        assert!(matches!(
+            Action::parse("Dividend (Mystery)"),
+            Some(Err(ImportError::UnknownAction(_)))
+        ));
+        assert!(parse("Market buy").is_none());
+    }
+
+    #[test]
+    fn reads_the_account_currency() {
+        let export = format!(
+            "{HEADER}
+Dividend (Dividend),2023-03-10 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,0.83,0.25,GBP,0.05,USD
+Dividend (Return of capital),2023-03-11 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,0.83,0.25,GBP,,
+Dividend (Mystery),2023-03-13 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,0.83,0.25,GBP,,
+"
+        );
+        let entries = read_export(&Trading212, "t212-gbp.csv", &export);
+        assert_eq!(entries.len(), 2);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(transaction.net, money(25, "GBP"));
+        assert_eq!(transaction.withheld, Some(money(5, "USD")));
+        assert_eq!(entries[1].line, 4);
+        assert!(matches!(
+            entries[1].transaction,
+            Err(ImportError::UnknownAction(_))
+        ));
+    }
+
+    #[test]
+    fn reads_the_currency_from_old_total_headers() {
+        let header = "Action,Time,ISIN,Ticker,Name,Total (EUR),Withholding tax,\
+                      Currency (Withholding tax)";
+        assert!(Trading212.detect(header));
+        let export = format!(
+            "{header}\nDividend (Ordinary),2023-03-10 10:12:13,US0378331005,AAPL,Apple,0.30,0.05,USD\n"
+        );
+        let entries = read_export(&Trading212, "t212-old.csv", &export);
+        let transaction = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(transaction.net, money(30, "EUR"));
+        assert_eq!(transaction.isin.as_deref(), Some("US0378331005"));
+    }
+
+    #[test]
+    fn nets_adjustments_against_the_dividend() {
+        let export = format!(
+            "{HEADER}
+Dividend (Ordinary),2023-03-10 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,1.07,0.30,EUR,0.05,USD
+Dividend (Ordinary),2023-06-10 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,1.07,0.30,EUR,0.05,USD
+Dividend (Adjustment),2023-03-20 10:12:13,US0378331005,AAPL,Apple,1.5,0.23,USD,1.07,-0.10,EUR,-0.01,USD
+Dividend (Adjustment),2023-03-20 10:12:13,US1912161007,KO,Coca Cola,1.5,0.23,USD,1.07,-0.10,EUR,,
+Dividend (Adjustment),2023-04-20 10:12:13,US1912161007,KO,Coca Cola,1.5,0.23,USD,1.07,0.20,EUR,,
+"
+        );
+        let entries = read_export(&Trading212, "t212-adjustments.csv", &export);
+        assert_eq!(entries.len(), 4);
+
+        let march = entries[0].transaction.as_ref().unwrap();
+        assert_eq!(march.net, money(20, "EUR"));
+        assert_eq!(march.withheld, Some(money(4, "USD")));
+        let june = entries[1].transaction.as_ref().unwrap();
+        assert_eq!(june.net, money(30, "EUR"));
+
+        // A correction without a dividend to correct.
+        assert_eq!(entries[2].line, 5);
+        assert!(matches!(
+            entries[2].transaction,
+            Err(ImportError::Unmatched(_))
+        ));
+        let bonus = entries[3].transaction.as_ref().unwrap();
+        assert_eq!(bonus.net, money(20, "EUR"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, Write};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    diagnostics::PayerInfo,
+    isin::{isin_country, validate_isin},
+    registry::{Registry, TickerAlias},
+    Broker,
+};
+
+/// Asks for the missing registry data of a payer and stores the answers in `registry`. Returns
+/// `false` when the user skips the payer by leaving the address empty.
+pub fn prompt_for_payer(
+    registry: &mut Registry,
+    broker: Broker,
+    payer: &PayerInfo,
+) -> Result<bool> {
+    let existing = registry.resolve(payer.isin.as_deref(), &payer.ticker, broker);
+    let mut security = existing.cloned().unwrap_or_default();
+
+    eprintln!();
+    eprintln!("Unknown payer in the {broker} export:");
+    eprintln!("  Ticker:   {}", payer.ticker);
+    eprintln!("  ISIN:     {}", payer.isin.as_deref().unwrap_or("-"));
+    eprintln!("  Name:     {}", payer.name.as_deref().unwrap_or("-"));
+    eprintln!("  Currency: {}", payer.currency.as_deref().unwrap_or("-"));
+
+    if security.isin.is_none() {
+        let isin = match &payer.isin {
+            Some(isin) => isin.clone(),
+            None => ask("ISIN", None, validate_isin)?,
+        };
+        match registry.by_isin(&isin) {
+            Some(known) => security = known.clone(),
+            None => security.isin = Some(isin),
+        }
+    }
+    if security.names.is_empty() {
+        let name = ask("Name", payer.name.as_deref(), validate_not_empty)?;
+        security.names.push(name);
+    }
+    if security.address.is_none() {
+        let address = ask("Address (empty to skip)", None, |_| Ok(()))?;
+        if address.is_empty() {
+            return Ok(false);
+        }
+        security.address = Some(address);
+    }
+    if security.payer_country.is_none() {
+        let inferred = security.isin.as_deref().and_then(isin_country);
+        let country = ask(
+            "Payer country (ISO 3166 code, e.g. US)",
+            inferred,
+            validate_country,
+        )?;
+        security.payer_country = Some(country.to_ascii_uppercase());
+    }
+    if security.source_country.is_none() {
+        let payer_country = security.payer_country.clone().unwrap_or_default();
+        let country = ask("Source country", Some(&payer_country), validate_country)?;
+        let country = country.to_ascii_uppercase();
+        if country != payer_country {
+            security.source_country = Some(country);
+        }
+    }
+    if !security
+        .tickers
+        .iter()
+        .any(|alias| alias.ticker == payer.ticker)
+    {
+        security.tickers.push(TickerAlias {
+            ticker: payer.ticker.clone(),
+            broker: Some(broker),
+            exchange: None,
+        });
+    }
+
+    registry.upsert(security);
+    Ok(true)
+}
+
+/// Prompts until `validate` accepts the answer, or `default` is taken on an empty one.
+fn ask(
+    label: &str,
+    default: Option<&str>,
+    validate: impl Fn(&str) -> Result<()>,
+) -> Result<String> {
+    let stdin = io::stdin();
+    loop {
+        match default {
+            Some(default) => eprint!("{label} [{default}]: "),
+            None => eprint!("{label}: "),
+        }
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer)? == 0 {
+            bail!("Input closed while asking for {label}");
+        }
+        let answer = match (answer.trim(), default) {
+            ("", Some(default)) => default.to_owned(),
+            (answer, _) => answer.to_owned(),
+        };
+        match validate(&answer) {
+            Ok(()) => return Ok(answer),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+}
+
+fn validate_not_empty(value: &str) -> Result<()> {
+    if value.is_empty() {
+        bail!("A value is required");
+    }
+    Ok(())
+}
+
+fn validate_country(value: &str) -> Result<()> {
+    if value.len() != 2 || !value.chars().all(|c| c.is_ascii_alphabetic()) {
+        bail!("Expected a two letter country code");
+    }
+    Ok(())
+}
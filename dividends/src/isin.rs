@@ -0,0 +1,101 @@
+use anyhow::{bail, Result};
+
+/// Validates an ISIN: a two letter prefix, nine alphanumeric characters and a Luhn check digit
+/// computed over the code with letters expanded to numbers (A = 10 … Z = 35).
+pub fn validate_isin(isin: &str) -> Result<()> {
+    let valid_format = isin.len() == 12
+        && isin
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+        && isin[..2].chars().all(|c| c.is_ascii_uppercase())
+        && isin[11..].chars().all(|c| c.is_ascii_digit());
+    if !valid_format {
+        bail!("Expected a 12 character ISIN, e.g. US0378331005, got {isin}");
+    }
+
+    let digits: Vec<u32> = isin
+        .chars()
+        .flat_map(|c| {
+            let value = c.to_digit(36).unwrap_or_default();
+            if value < 10 {
+                vec![value]
+            } else {
+                vec![value / 10, value % 10]
+            }
+        })
+        .collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(position, &digit)| match position % 2 {
+            0 => digit,
+            _ if digit * 2 > 9 => digit * 2 - 9,
+            _ => digit * 2,
+        })
+        .sum();
+    if !sum.is_multiple_of(10) {
+        bail!("ISIN {isin} has an invalid check digit");
+    }
+    Ok(())
+}
+
+/// The country an ISIN was issued in, taken from its prefix. Prefixes that do not name a
+/// country, like XS for international bonds or EU, yield `None`.
+pub fn isin_country(isin: &str) -> Option<&str> {
+    let prefix = isin.get(..2)?;
+    if !prefix.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let user_assigned = matches!(
+        prefix.as_bytes(),
+        b"AA" | b"ZZ" | b"EU" | [b'Q', b'M'..=b'Z'] | [b'X', _]
+    );
+    (!user_assigned).then_some(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_isins() {
+        for isin in [
+            "US0378331005",
+            "US1912161007",
+            "IE00B3XXRP09",
+            "NL0010273215",
+            "DE0007164600",
+            // A sum that is already a multiple of ten gives a check digit of 0, not 10.
+            "CH0038863350",
+            "US4642872000",
+        ] {
+            assert!(validate_isin(isin).is_ok(), "{isin}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_isins() {
+        for isin in [
+            "US0378331006",
+            "IE00B3XXRP08",
+            "US4642872001",
+            "US037833100",
+            "us0378331005",
+            "US037833100X",
+            "120378331005",
+        ] {
+            assert!(validate_isin(isin).is_err(), "{isin}");
+        }
+    }
+
+    #[test]
+    fn takes_the_country_from_the_prefix() {
+        assert_eq!(isin_country("US0378331005"), Some("US"));
+        assert_eq!(isin_country("IE00B3XXRP09"), Some("IE"));
+        assert_eq!(isin_country("XS1234567890"), None);
+        assert_eq!(isin_country("EU000A1G0AB4"), None);
+        assert_eq!(isin_country("QS0000000000"), None);
+        assert_eq!(isin_country("U"), None);
+    }
+}
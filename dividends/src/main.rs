@@ -1,311 +1,485 @@
+mod calculation;
+mod cli;
+mod currency;
+mod dates;
+mod diagnostics;
+mod importers;
+mod interactive;
+mod isin;
+mod money;
+mod output;
+mod rates;
+mod registry;
+mod summary;
+mod tax_number;
+mod treaty;
+mod withholding;
+
 use std::{
-    collections::HashMap,
-    env,
-    fs::{read_to_string, File},
-    io::{self, BufReader, Write},
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
-use anyhow::Result;
-use csv::Reader;
-use serde::Deserialize;
+use anyhow::{bail, Context, Result};
+use calculation::calculate;
+use chrono::{Datelike, Local, NaiveDate};
+use clap::Parser;
+use cli::{
+    Cli, Command, DivArgs, InputArgs, RateArgs, RatesArgs, RegistryArgs, RegistryCommand,
+    RegistryFileArgs, ReportArgs, TaxArgs,
+};
+use diagnostics::Diagnostics;
+use env_logger::Env;
+use importers::{importer, trading212_listings, ImportContext};
+use money::Money;
+use output::{write_output, OutputFormat};
+use rates::RateTable;
+use registry::{Learned, Registry, Security};
+use serde::{Deserialize, Serialize};
+use summary::{summarize, write_summary, Grouping};
+use tax_number::validate_tax_number;
+use treaty::TreatyTable;
+use withholding::Withholding;
 
 struct Dividend {
-    date: String,
+    date: NaiveDate,
+    payer_tax_number: Option<String>,
     payer_id: String,
     name: String,
     address: String,
-    country: String,
-    amount: String,
-    tax: String,
+    payer_country: String,
+    source_country: String,
+    amount: Money,
+    /// Foreign tax claimed in the return, capped at the treaty rate.
+    tax: Money,
+    /// Foreign tax actually withheld, or estimated as described by `withholding`.
+    withheld: Money,
+    withholding: Withholding,
+    /// Statement for the "uveljavljam oprostitev po mednarodni pogodbi" column.
+    relief: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct RatesByDay {
-    #[serde(rename = "$value")]
-    days: Vec<Rates>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Broker {
+    Revolut,
+    Trading212,
+    #[serde(rename = "ibkr")]
+    InteractiveBrokers,
+    Degiro,
 }
 
-#[derive(Deserialize)]
-struct Rates {
-    #[serde(rename = "datum")]
-    date: String,
-    #[serde(rename = "$value")]
-    values: Vec<Rate>,
+impl fmt::Display for Broker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Broker::Revolut => "Revolut",
+            Broker::Trading212 => "Trading 212",
+            Broker::InteractiveBrokers => "Interactive Brokers",
+            Broker::Degiro => "DEGIRO",
+        })
+    }
 }
 
-#[derive(Deserialize)]
-struct Rate {
-    #[serde(rename = "oznaka")]
-    name: String,
-    #[serde(rename = "$value")]
-    rate: f32,
+fn main() -> Result<ExitCode> {
+    env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init();
+
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Div(args) => run_div(args),
+        Command::Rates(args) => run_rates(args).map(|_| ExitCode::SUCCESS),
+        Command::Registry(args) => run_registry(args).map(|_| ExitCode::SUCCESS),
+        Command::Report(args) => run_report(args),
+        Command::Tax(args) => run_tax(args),
+    }
 }
 
-fn main() -> Result<()> {
-    env_logger::init();
+fn run_div(args: DivArgs) -> Result<ExitCode> {
+    let tax_id = match args.tax_id {
+        Some(tax_id) => tax_id,
+        None => {
+            eprint!("Enter your tax id: ");
+            let mut tax_id = String::new();
+            io::stdin().read_line(&mut tax_id)?;
+            tax_id
+        }
+    };
+    let tax_id = tax_id.trim();
+    validate_tax_number(tax_id).context("Invalid taxpayer tax number")?;
+
+    let format = args
+        .format
+        .or_else(|| args.out.as_deref().and_then(OutputFormat::from_path))
+        .unwrap_or(OutputFormat::Csv);
+    let out = args
+        .out
+        .unwrap_or_else(|| format.default_file_name().into());
+
+    let mut diagnostics = Diagnostics::default();
+    let dividends = load_dividends(&args.inputs, args.year, &mut diagnostics)?;
+    diagnostics.print_summary();
+    if args.strict && !diagnostics.is_empty() {
+        bail!(
+            "Refusing to write the return, {} rows were dropped",
+            diagnostics.len()
+        );
+    }
+    validate_payer_tax_numbers(&dividends)?;
 
-    let stdin = io::stdin();
-    log::info!("Enter your tax id: ");
+    let returns = if args.split_by_year {
+        split_by_year(dividends)
+            .into_iter()
+            .map(|(year, dividends)| (year, path_for_year(&out, year), dividends))
+            .collect()
+    } else {
+        let year = match args.year {
+            Some(year) => year,
+            None => tax_year(&dividends)?,
+        };
+        vec![(year, out, dividends)]
+    };
 
-    let mut tax_id = String::new();
-    stdin.read_line(&mut tax_id)?;
+    for (year, out, dividends) in returns {
+        write_output(format, &out, tax_id, year, &dividends)?;
+        log::info!(
+            "Wrote {} dividends for {year} to {}",
+            dividends.len(),
+            out.display()
+        );
+    }
 
-    log::info!("Loading addresses");
-    let places = load_places()?;
-    log::info!("Loading rates");
-    let rates = load_rates()?;
+    Ok(diagnostics.exit_code())
+}
 
-    let mut args = env::args();
-    let mut dividends = vec![];
-    args.next();
-    let revolut = args.next().unwrap();
-    let revolut_dividends = load_revolut_dividends(&places, &rates, &revolut)?;
-    dividends.extend(revolut_dividends);
-    if let Some(t212) = args.next() {
-        let t212_dividends = load_t212_dividends(&places, &rates, &t212)?;
-        dividends.extend(t212_dividends);
+/// The tax year of the dividends when none was given. Exports often reach into the next year, so
+/// dividends spanning several years are refused rather than guessing which one is meant; without
+/// dividends it is the previous calendar year.
+fn tax_year(dividends: &[Dividend]) -> Result<i32> {
+    let years: BTreeSet<_> = dividends.iter().map(|d| d.date.year()).collect();
+    match years.len() {
+        0 => Ok(Local::now().year() - 1),
+        1 => Ok(years.into_iter().next().expect("one year")),
+        _ => bail!("Dividends span the years {years:?}, pick the tax year with --year"),
     }
+}
 
-    write_output(tax_id.trim(), &dividends)?;
+fn split_by_year(dividends: Vec<Dividend>) -> BTreeMap<i32, Vec<Dividend>> {
+    let mut years: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for dividend in dividends {
+        years
+            .entry(dividend.date.year())
+            .or_default()
+            .push(dividend);
+    }
+    years
+}
 
-    Ok(())
+/// Turns `result.xml` into `result-2023.xml`.
+fn path_for_year(path: &Path, year: i32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}-{year}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{year}"),
+    };
+    path.with_file_name(file_name)
 }
 
-fn load_places() -> Result<HashMap<String, (String, String)>> {
-    let places = read_to_string("places.json")?;
-    serde_json::from_str(&places).map_err(|e| e.into())
+fn run_rates(args: RatesArgs) -> Result<()> {
+    let rates = load_rates(&args.rates)?;
+    let currency = Money::zero(&args.currency).to_major_unit().currency;
+    let Some((published, rate)) = rates.lookup(args.date, &currency) else {
+        bail!("No {currency} rate available for {}", args.date);
+    };
+    println!("{currency} {rate} per EUR, published on {published}");
+    if let Some(amount) = args.amount {
+        let money = Money::new(amount, &args.currency);
+        let eur = rates
+            .foreign_to_eur(args.date, &money)
+            .context("Unable to convert amount")?;
+        println!(
+            "{amount} {} = {:.2} EUR",
+            args.currency,
+            eur.round_cents().amount
+        );
+    }
+    if let Some(amount) = args.eur_amount {
+        let foreign = rates
+            .eur_to_foreign(args.date, &Money::eur(amount), &args.currency)
+            .context("Unable to convert amount")?;
+        println!(
+            "{amount} EUR = {:.2} {}",
+            foreign.round_cents().amount,
+            args.currency
+        );
+    }
+    Ok(())
 }
 
-fn load_rates() -> Result<HashMap<String, HashMap<String, f32>>> {
-    let rates = File::open("rates.xml")?;
-    let rates: RatesByDay = serde_xml_rs::from_reader(rates)?;
-    Ok(rates
-        .days
-        .iter()
-        .map(|day| {
-            (
-                day.date.clone(),
-                day.values
+fn run_registry(args: RegistryArgs) -> Result<()> {
+    let path = &args.registry.registry_file;
+    if let RegistryCommand::Migrate {
+        places,
+        revolut_info,
+        force,
+    } = &args.command
+    {
+        if path.exists() && !force {
+            bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            );
+        }
+        let registry = Registry::load_legacy(places, revolut_info)?;
+        registry.save(path)?;
+        println!(
+            "Wrote {} securities to {}",
+            registry.securities().count(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let mut registry = load_registry(&args.registry)?;
+    match args.command {
+        RegistryCommand::List => {
+            let mut securities: Vec<_> = registry.securities().collect();
+            securities.sort_by(|a, b| a.isin.cmp(&b.isin));
+            for security in securities {
+                let tickers: Vec<_> = security
+                    .tickers
                     .iter()
-                    .map(|rate| (rate.name.clone(), rate.rate))
+                    .map(|alias| alias.ticker.as_str())
+                    .collect();
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    security.isin.as_deref().unwrap_or("-"),
+                    tickers.join(","),
+                    security.payer_country().unwrap_or("-"),
+                    security.name().unwrap_or("-"),
+                );
+            }
+        }
+        RegistryCommand::Show { id } => {
+            let securities: Vec<_> = match registry.by_isin(&id) {
+                Some(security) => vec![security],
+                None => registry
+                    .securities()
+                    .filter(|security| security.tickers.iter().any(|alias| alias.ticker == id))
                     .collect(),
-            )
-        })
-        .collect())
+            };
+            if securities.is_empty() {
+                bail!("No registry entry for {id}");
+            }
+            for security in securities {
+                print_security(security);
+            }
+        }
+        RegistryCommand::Learn { t212 } => {
+            let mut added = 0;
+            let mut updated = 0;
+            for path in &t212 {
+                for listing in trading212_listings(path)? {
+                    match registry.learn(&listing) {
+                        Learned::Added => added += 1,
+                        Learned::Updated => updated += 1,
+                        Learned::Unchanged => {}
+                    }
+                }
+            }
+            registry.save(path)?;
+            println!(
+                "Added {added} and updated {updated} securities in {}",
+                path.display()
+            );
+        }
+        RegistryCommand::Migrate { .. } => unreachable!("handled above"),
+    }
+    Ok(())
 }
 
-fn load_t212_dividends(
-    places: &HashMap<String, (String, String)>,
-    rates: &HashMap<String, HashMap<String, f32>>,
-    trading212: &str,
-) -> Result<Vec<Dividend>> {
-    let file = File::open(trading212)?;
-    let reader = BufReader::new(file);
-    let mut reader = Reader::from_reader(reader);
-
-    let headers: HashMap<_, _> = reader
-        .headers()?
-        .iter()
-        .enumerate()
-        .map(|(i, v)| (v.to_owned(), i))
-        .collect();
-
-    let mut dividends = vec![];
-    for record in reader.records() {
-        let record = record?;
-        if record.get(headers["Action"]) != Some("Dividend (Ordinary)") {
-            continue;
+fn print_security(security: &Security) {
+    println!("ISIN:    {}", security.isin.as_deref().unwrap_or("-"));
+    println!("Name:    {}", security.names.join(" / "));
+    println!("Address: {}", security.address.as_deref().unwrap_or("-"));
+    println!(
+        "Payer country:  {}",
+        security.payer_country().unwrap_or("-")
+    );
+    println!(
+        "Source country: {}",
+        security.source_country().unwrap_or("-")
+    );
+    if let Some(tax_number) = &security.payer_tax_number {
+        println!("Tax number: {tax_number}");
+    }
+    if let Some(lei) = &security.lei {
+        println!("LEI:     {lei}");
+    }
+    if let Some(security_type) = security.security_type {
+        println!("Type:    {security_type:?}");
+    }
+    if let Some(relief) = &security.relief {
+        println!("Relief:  {relief}");
+    }
+    for alias in &security.tickers {
+        let broker = alias
+            .broker
+            .map_or_else(|| "any broker".to_owned(), |broker| broker.to_string());
+        match &alias.exchange {
+            Some(exchange) => println!("Ticker:  {} ({broker}, {exchange})", alias.ticker),
+            None => println!("Ticker:  {} ({broker})", alias.ticker),
         }
+    }
+    println!();
+}
 
-        let Some(date) = record.get(headers["Time"]) else {
-            log::info!("Date was not present");
-            continue;
-        };
-        let Some(isin) = record.get(headers["ISIN"]) else {
-            log::info!("Payer was not present");
-            continue;
-        };
-        let Some(name) = record.get(headers["Name"]) else {
-            log::info!("Missing payer name");
-            continue;
-        };
-        let Some(value) = record.get(headers["Total"]) else {
-            log::info!("Missing dividend EUR value");
-            continue;
+fn run_report(args: ReportArgs) -> Result<ExitCode> {
+    let mut diagnostics = Diagnostics::default();
+    let dividends = load_dividends(&args.inputs, args.year, &mut diagnostics)?;
+    if !args.rows {
+        let groupings = match args.by.as_slice() {
+            [] => vec![Grouping::Payer, Grouping::Country, Grouping::Month],
+            by => by.to_vec(),
         };
-        let Some(witholding_tax) = record.get(headers["Withholding tax"]) else {
-            log::info!("Missing witholding tax");
-            continue;
-        };
-        let Some(witholding_tax_currency) = record.get(headers["Currency (Withholding tax)"])
-        else {
-            log::info!("Missing witholding tax currency");
-            continue;
-        };
-        let Some(ticker) = record.get(headers["Ticker"]) else {
-            log::info!("Missing ticker");
-            continue;
-        };
-        let Some((address, country)) = company_address(ticker, places) else {
-            log::error!("No address for ISIN {isin}, {ticker}, {name}");
-            continue;
-        };
-        let Some(tax) = convert_value(date, witholding_tax, witholding_tax_currency, rates) else {
-            log::error!("Did not find an exchange rate for {witholding_tax_currency}!");
-            continue;
-        };
-        dividends.push(Dividend {
-            date: date.to_owned(),
-            payer_id: isin.to_owned(),
-            name: name.to_owned(),
-            address,
-            country,
-            amount: value.to_owned(),
-            tax,
-        });
+        let summary: Vec<_> = groupings
+            .into_iter()
+            .flat_map(|grouping| summarize(&dividends, grouping))
+            .collect();
+        match &args.out {
+            Some(out) => {
+                let mut output = BufWriter::new(File::create(out)?);
+                write_summary(args.format, &mut output, &summary)?;
+                output.flush()?;
+            }
+            None => write_summary(args.format, &mut io::stdout().lock(), &summary)?,
+        }
+        diagnostics.print_summary();
+        return Ok(diagnostics.exit_code());
     }
-    Ok(dividends)
-}
 
-fn load_revolut_dividends(
-    places: &HashMap<String, (String, String)>,
-    rates: &HashMap<String, HashMap<String, f32>>,
-    revolut: &str,
-) -> Result<Vec<Dividend>> {
-    let file = File::open(revolut)?;
-    let reader = BufReader::new(file);
-    let mut reader = Reader::from_reader(reader);
+    for dividend in &dividends {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            dividend.date,
+            dividend.payer_id,
+            dividend.name,
+            dividend.payer_country,
+            dividend.source_country,
+            dividend.amount.to_xml_string(),
+            dividend.withheld.to_xml_string(),
+            dividend.tax.to_xml_string(),
+            dividend.withholding
+        );
+    }
+    diagnostics.print_summary();
+    Ok(diagnostics.exit_code())
+}
 
-    let headers: HashMap<_, _> = reader
-        .headers()?
-        .iter()
-        .enumerate()
-        .map(|(i, v)| (v.to_owned(), i))
-        .collect();
+fn run_tax(args: TaxArgs) -> Result<ExitCode> {
+    let mut diagnostics = Diagnostics::default();
+    let dividends = load_dividends(&args.inputs, args.year, &mut diagnostics)?;
+    let year = match args.year {
+        Some(year) => year,
+        None => tax_year(&dividends)?,
+    };
 
-    let revolut_info = load_revolut_info()?;
+    let calculation = calculate(year, &dividends);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&calculation)?);
+    } else {
+        calculation.print_summary();
+    }
+    diagnostics.print_summary();
+    Ok(diagnostics.exit_code())
+}
 
-    let mut dividends = vec![];
-    for record in reader.records() {
-        let record = record?;
-        if record.get(headers["Type"]) != Some("DIVIDEND") {
-            continue;
+fn validate_payer_tax_numbers(dividends: &[Dividend]) -> Result<()> {
+    for dividend in dividends
+        .iter()
+        .filter(|dividend| dividend.payer_country == "SI")
+    {
+        match &dividend.payer_tax_number {
+            Some(tax_number) => validate_tax_number(tax_number)
+                .with_context(|| format!("Invalid tax number for payer {}", dividend.name))?,
+            None => log::warn!("Slovenian payer {} has no tax number", dividend.name),
         }
-        let Some(date) = record.get(headers["Date"]) else {
-            log::error!("Missing dividend date");
-            continue;
-        };
-        let Some(ticker) = record.get(headers["Ticker"]) else {
-            log::error!("Missing ticker");
-            continue;
-        };
-        let Some((address, country)) = company_address(ticker, places) else {
-            log::error!("No address for {ticker}");
-            continue;
-        };
-        let Some(amount) = record.get(headers["Total Amount"]) else {
-            log::error!("Missing amount");
-            continue;
-        };
-        let amount = amount.replace('$', "");
-        let Some(amount) = convert_value(date, &amount, "USD", rates) else {
-            log::error!("Unable to convert value");
-            continue;
-        };
-        let Some((isin, name)) = revolut_info.get(ticker) else {
-            log::error!("Missing revolut definition for {ticker}");
-            continue;
-        };
-
-        dividends.push(Dividend {
-            date: date.to_owned(),
-            payer_id: isin.to_string(),
-            name: name.to_string(),
-            address,
-            country,
-            amount,
-            tax: "0.00".to_string(),
-        })
     }
-
-    Ok(dividends)
+    Ok(())
 }
 
-fn load_revolut_info() -> Result<HashMap<String, (String, String)>> {
-    let places = read_to_string("revolut.json")?;
-    serde_json::from_str(&places).map_err(|e| e.into())
+fn load_rates(args: &RateArgs) -> Result<RateTable> {
+    log::info!("Loading rates");
+    Ok(RateTable::load_bsi(&args.rates_file)
+        .with_context(|| format!("Unable to load rates from {}", args.rates_file.display()))?
+        .with_max_lookback_days(args.max_rate_lookback))
 }
 
-fn write_output(tax_id: &str, dividends: &[Dividend]) -> Result<()> {
-    let mut output = File::create("result.csv")?;
-
-    writeln!(
-        output,
-        "#FormCode;Version;TaxPayerID;TaxPayerType;DocumentWorkflowID;;;;;;\n"
-    )?;
-    writeln!(output, "DOH-DIV;3.9;{tax_id};FO;O;;;;;;\n")?;
-    writeln!(output, "#datum prejema dividende;davčna številka izplačevalca dividend;identifikacijska  številka izplačevalca dividend;naziv izplačevalca dividend;naslov izplačevalca dividend;država izplačevalca dividend;vrsta dividende;znesek dividend;tuji davek;država vira;uveljavljam oprostitev po mednarodni pogodbi\n")?;
+/// Imports the dividends of every broker export given on the command line, received in `year`
+/// when one is given.
+fn load_dividends(
+    inputs: &InputArgs,
+    year: Option<i32>,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Dividend>> {
+    let mut exports = vec![];
+    for path in &inputs.files {
+        exports.push((importers::detect(path)?, path));
+    }
+    for (broker, paths) in [
+        (Broker::Revolut, &inputs.revolut),
+        (Broker::Trading212, &inputs.t212),
+        (Broker::InteractiveBrokers, &inputs.ibkr),
+        (Broker::Degiro, &inputs.degiro),
+    ] {
+        exports.extend(paths.iter().map(|path| (importer(broker), path)));
+    }
+    if exports.is_empty() {
+        bail!("No broker exports given");
+    }
 
-    for dividend in dividends {
-        let Some(date) = dividend.date.split(&[' ', 'T']).next() else {
-            log::error!("Unable to convert date");
-            continue;
-        };
-        let date = date
-            .split('-')
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .collect::<Vec<_>>()
-            .join(".");
-        let amount = dividend.amount.replace('.', ",");
+    let mut context = ImportContext {
+        registry: load_registry(&inputs.registry)?,
+        registry_file: inputs.registry.registry_file.clone(),
+        rates: load_rates(&inputs.rates)?,
+        treaties: load_treaties(inputs.treaties.as_deref())?,
+        interactive: inputs.interactive,
+        year,
+        skipped_payers: HashSet::new(),
+    };
 
-        writeln!(
-            output,
-            "{};;{};{};{};{};1;{};{};{};",
-            date,
-            dividend.payer_id,
-            dividend.name,
-            dividend.address,
-            dividend.country,
-            amount,
-            dividend.tax,
-            dividend.country
-        )?;
+    let mut dividends = vec![];
+    for (importer, path) in exports {
+        log::info!(
+            "Importing {} as a {} export",
+            path.display(),
+            importer.broker()
+        );
+        dividends.extend(context.import(importer, path, diagnostics)?);
     }
-    Ok(())
+    Ok(dividends)
 }
 
-fn company_address(
-    company_name: &str,
-    places: &HashMap<String, (String, String)>,
-) -> Option<(String, String)> {
-    places.get(company_name).cloned()
+fn load_treaties(overrides: Option<&Path>) -> Result<TreatyTable> {
+    let treaties = TreatyTable::builtin();
+    match overrides {
+        Some(path) => treaties.with_overrides(path),
+        None => Ok(treaties),
+    }
 }
 
-fn convert_value(
-    date: &str,
-    tax: &str,
-    mut currency: &str,
-    rates: &HashMap<String, HashMap<String, f32>>,
-) -> Option<String> {
-    if currency == "EUR" {
-        return Some(tax.replacen('.', ",", 1));
-    }
-    let mut tax: f32 = tax.parse().ok()?;
-    if currency == "GBX" {
-        currency = "GBP";
-        tax *= 100.0;
+fn load_registry(args: &RegistryFileArgs) -> Result<Registry> {
+    log::info!("Loading addresses");
+    let path = &args.registry_file;
+    if !path.exists() && Path::new("places.json").exists() {
+        log::warn!(
+            "{} not found, using places.json and revolut.json, \
+             run `dividends registry migrate` to convert them",
+            path.display()
+        );
+        return Registry::load_legacy("places.json", "revolut.json");
     }
-
-    let date = date.split(&[' ', 'T']).next()?;
-    let date_rate = match rates.get(date) {
-        Some(value) => value,
-        None => {
-            println!("Did not find currency entry for {date}");
-            return None;
-        }
-    };
-    let rate = date_rate.get(currency)?;
-    Some(format!("{:.2}", tax * rate).replacen('.', ",", 1))
+    Registry::load(path)
 }
@@ -0,0 +1,153 @@
+use std::ops::Add;
+
+use anyhow::{bail, Context, Result};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::currency::minor_unit;
+
+/// An exact amount in a given ISO 4217 currency.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: impl Into<String>) -> Self {
+        Self {
+            amount,
+            currency: currency.into(),
+        }
+    }
+
+    pub fn eur(amount: Decimal) -> Self {
+        Self::new(amount, "EUR")
+    }
+
+    pub fn zero(currency: impl Into<String>) -> Self {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    /// Parses a broker amount such as `1.23`, `$1.23` or `-1,234.56`. Amounts in minor-unit
+    /// currencies are converted to their major currency.
+    pub fn parse(value: &str, currency: impl Into<String>) -> Result<Self> {
+        let cleaned: String = value
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, '$' | '€' | '£' | ' '))
+            .collect();
+        if cleaned.is_empty() {
+            bail!("Empty amount");
+        }
+        // Commas may only separate thousands, a decimal comma would silently scale the amount.
+        let integer = cleaned.split('.').next().unwrap_or_default();
+        let mut groups = integer.trim_start_matches(['-', '+']).split(',');
+        let leading = groups.next().unwrap_or_default();
+        if integer.contains(',')
+            && (!(1..=3).contains(&leading.len()) || groups.any(|group| group.len() != 3))
+        {
+            bail!("Invalid amount {value}, commas may only separate thousands");
+        }
+        let amount = cleaned
+            .replace(',', "")
+            .parse()
+            .with_context(|| format!("Invalid amount {value}"))?;
+        Ok(Self::new(amount, currency).to_major_unit())
+    }
+
+    /// Converts amounts quoted in a minor unit, such as GBX, to the major currency.
+    pub fn to_major_unit(&self) -> Self {
+        match minor_unit(&self.currency) {
+            Some(unit) => Self::new(self.amount / Decimal::from(unit.per_major), unit.major),
+            None => self.clone(),
+        }
+    }
+
+    pub fn is_eur(&self) -> bool {
+        self.currency == "EUR"
+    }
+
+    /// Rounds half-up (away from zero) to whole cents, as FURS expects.
+    pub fn round_cents(&self) -> Self {
+        Self::new(
+            self.amount
+                .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero),
+            self.currency.clone(),
+        )
+    }
+
+    /// Formats the amount with two decimals and a decimal comma, as used in eDavki CSV imports.
+    pub fn to_csv_string(&self) -> String {
+        format!("{:.2}", self.round_cents().amount).replace('.', ",")
+    }
+
+    /// Formats the amount with two decimals and a decimal point, as used in eDavki XML imports.
+    pub fn to_xml_string(&self) -> String {
+        format!("{:.2}", self.round_cents().amount)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        assert_eq!(
+            self.currency, other.currency,
+            "Adding mismatched currencies"
+        );
+        Money::new(self.amount + other.amount, self.currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Option<Money> {
+        Money::parse(value, "USD").ok()
+    }
+
+    fn usd(amount: i64, scale: u32) -> Money {
+        Money::new(Decimal::new(amount, scale), "USD")
+    }
+
+    #[test]
+    fn parses_broker_amounts() {
+        assert_eq!(parse("1.23"), Some(usd(123, 2)));
+        assert_eq!(parse(" $1.23 "), Some(usd(123, 2)));
+        assert_eq!(parse("-1,234.56"), Some(usd(-123456, 2)));
+        assert_eq!(parse("1,234,567"), Some(usd(1234567, 0)));
+        assert_eq!(parse("€ 0.5"), Some(usd(5, 1)));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("n/a"), None);
+    }
+
+    #[test]
+    fn rejects_decimal_commas() {
+        assert_eq!(parse("1,23"), None);
+        assert_eq!(parse("-0,36"), None);
+        assert_eq!(parse("1,2345.00"), None);
+        assert_eq!(parse("1234,567.00"), None);
+        assert_eq!(parse(",123"), None);
+    }
+
+    #[test]
+    fn converts_minor_units() {
+        assert_eq!(
+            Money::parse("123.4", "GBX").unwrap(),
+            Money::new(Decimal::new(1234, 3), "GBP")
+        );
+    }
+
+    #[test]
+    fn rounds_half_up_to_cents() {
+        let round = |amount, scale| usd(amount, scale).round_cents();
+        assert_eq!(round(1005, 3), usd(101, 2));
+        assert_eq!(round(1004, 3), usd(100, 2));
+        assert_eq!(round(-1005, 3), usd(-101, 2));
+        assert_eq!(round(-1004, 3), usd(-100, 2));
+        assert_eq!(round(5, 3), usd(1, 2));
+        assert_eq!(usd(-36, 2).to_csv_string(), "-0,36");
+        assert_eq!(usd(1, 0).to_xml_string(), "1.00");
+    }
+}
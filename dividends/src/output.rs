@@ -0,0 +1,297 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::Dividend;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Csv,
+    Xml,
+}
+
+impl OutputFormat {
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "result.csv",
+            OutputFormat::Xml => "result.xml",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Self::from_str(extension, true).ok()
+    }
+}
+
+pub fn write_output(
+    format: OutputFormat,
+    path: &Path,
+    tax_id: &str,
+    year: i32,
+    dividends: &[Dividend],
+) -> Result<()> {
+    let output = File::create(path)?;
+    let mut output = BufWriter::new(output);
+    match format {
+        OutputFormat::Csv => write_csv(&mut output, tax_id, dividends)?,
+        OutputFormat::Xml => write_xml(&mut output, tax_id, year, dividends)?,
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn write_csv(output: &mut impl Write, tax_id: &str, dividends: &[Dividend]) -> Result<()> {
+    writeln!(
+        output,
+        "#FormCode;Version;TaxPayerID;TaxPayerType;DocumentWorkflowID;;;;;;\n"
+    )?;
+    writeln!(output, "DOH-DIV;3.9;{tax_id};FO;O;;;;;;\n")?;
+    writeln!(output, "#datum prejema dividende;davčna številka izplačevalca dividend;identifikacijska  številka izplačevalca dividend;naziv izplačevalca dividend;naslov izplačevalca dividend;država izplačevalca dividend;vrsta dividende;znesek dividend;tuji davek;država vira;uveljavljam oprostitev po mednarodni pogodbi\n")?;
+
+    for dividend in dividends {
+        writeln!(
+            output,
+            "{};{};{};{};{};{};1;{};{};{};{}",
+            dividend.date.format("%d.%m.%Y"),
+            dividend.payer_tax_number.as_deref().unwrap_or_default(),
+            dividend.payer_id,
+            dividend.name,
+            dividend.address,
+            dividend.payer_country,
+            dividend.amount.to_csv_string(),
+            dividend.tax.to_csv_string(),
+            dividend.source_country,
+            dividend.relief.as_deref().unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+fn write_xml(
+    output: &mut impl Write,
+    tax_id: &str,
+    year: i32,
+    dividends: &[Dividend],
+) -> Result<()> {
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<Envelope xmlns="http://edavki.durs.si/Documents/Schemas/Doh_Div_3.xsd" xmlns:edp="http://edavki.durs.si/Documents/Schemas/EDP-Common-1.xsd">"#
+    )?;
+    writeln!(output, "  <edp:Header>")?;
+    writeln!(output, "    <edp:taxpayer>")?;
+    writeln!(
+        output,
+        "      <edp:taxNumber>{}</edp:taxNumber>",
+        escape_xml(tax_id)
+    )?;
+    writeln!(output, "      <edp:taxpayerType>FO</edp:taxpayerType>")?;
+    writeln!(output, "    </edp:taxpayer>")?;
+    writeln!(output, "  </edp:Header>")?;
+    writeln!(output, "  <edp:AttachmentList />")?;
+    writeln!(output, "  <edp:Signatures />")?;
+    writeln!(output, "  <body>")?;
+    writeln!(output, "    <edp:bodyContent />")?;
+    writeln!(output, "    <Doh_Div>")?;
+    writeln!(output, "      <Period>{year}</Period>")?;
+    writeln!(output, "      <ResidentCountry>SI</ResidentCountry>")?;
+    writeln!(output, "      <IsResident>true</IsResident>")?;
+    writeln!(output, "      <SelfReport>false</SelfReport>")?;
+    writeln!(output, "    </Doh_Div>")?;
+
+    for dividend in dividends {
+        writeln!(output, "    <Dividend>")?;
+        writeln!(
+            output,
+            "      <Date>{}</Date>",
+            dividend.date.format("%Y-%m-%d")
+        )?;
+        if let Some(tax_number) = &dividend.payer_tax_number {
+            writeln!(
+                output,
+                "      <PayerTaxNumber>{}</PayerTaxNumber>",
+                escape_xml(tax_number)
+            )?;
+        }
+        writeln!(
+            output,
+            "      <PayerIdentificationNumber>{}</PayerIdentificationNumber>",
+            escape_xml(&dividend.payer_id)
+        )?;
+        writeln!(
+            output,
+            "      <PayerName>{}</PayerName>",
+            escape_xml(&dividend.name)
+        )?;
+        writeln!(
+            output,
+            "      <PayerAddress>{}</PayerAddress>",
+            escape_xml(&dividend.address)
+        )?;
+        writeln!(
+            output,
+            "      <PayerCountry>{}</PayerCountry>",
+            escape_xml(&dividend.payer_country)
+        )?;
+        writeln!(output, "      <Type>1</Type>")?;
+        writeln!(
+            output,
+            "      <Value>{}</Value>",
+            dividend.amount.to_xml_string()
+        )?;
+        writeln!(
+            output,
+            "      <ForeignTax>{}</ForeignTax>",
+            dividend.tax.to_xml_string()
+        )?;
+        writeln!(
+            output,
+            "      <SourceCountry>{}</SourceCountry>",
+            escape_xml(&dividend.source_country)
+        )?;
+        if let Some(relief) = &dividend.relief {
+            writeln!(
+                output,
+                "      <ReliefStatement>{}</ReliefStatement>",
+                escape_xml(relief)
+            )?;
+        }
+        writeln!(output, "    </Dividend>")?;
+    }
+
+    writeln!(output, "  </body>")?;
+    writeln!(output, "</Envelope>")?;
+    Ok(())
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{money::Money, withholding::Withholding};
+
+    fn dividends() -> Vec<Dividend> {
+        let apple = Dividend {
+            date: NaiveDate::from_ymd_opt(2023, 5, 18).unwrap(),
+            payer_tax_number: Some("942404110".to_owned()),
+            payer_id: "US0378331005".to_owned(),
+            name: "Apple Inc.".to_owned(),
+            address: "One Apple Park Way, Cupertino, CA 95014".to_owned(),
+            payer_country: "US".to_owned(),
+            source_country: "US".to_owned(),
+            amount: Money::eur(Decimal::new(123456, 3)),
+            tax: Money::eur(Decimal::new(1852, 2)),
+            withheld: Money::eur(Decimal::new(1852, 2)),
+            withholding: Withholding::Reported,
+            relief: Some("Article 10 of the convention".to_owned()),
+        };
+        let johnson = Dividend {
+            date: NaiveDate::from_ymd_opt(2023, 6, 6).unwrap(),
+            payer_tax_number: None,
+            payer_id: "US4781601046".to_owned(),
+            name: "Johnson & Johnson <JNJ>".to_owned(),
+            address: "One Johnson & Johnson Plaza, New Brunswick".to_owned(),
+            payer_country: "US".to_owned(),
+            source_country: "US".to_owned(),
+            amount: Money::eur(Decimal::new(1005, 3)),
+            tax: Money::eur(Decimal::ZERO),
+            withheld: Money::eur(Decimal::ZERO),
+            withholding: Withholding::Reported,
+            relief: None,
+        };
+        vec![apple, johnson]
+    }
+
+    fn written(write: impl FnOnce(&mut Vec<u8>) -> Result<()>) -> String {
+        let mut output = vec![];
+        write(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn writes_doh_div_xml() {
+        let xml = written(|output| write_xml(output, "12345679", 2023, &dividends()));
+        let position = |needle: &str| {
+            xml.find(needle)
+                .unwrap_or_else(|| panic!("{needle} missing from {xml}"))
+        };
+        assert!(position("<Envelope ") < position("<edp:Header>"));
+        assert!(position("<edp:taxNumber>12345679</edp:taxNumber>") < position("</edp:Header>"));
+        assert!(position("</edp:Header>") < position("<body>"));
+        assert!(position("<body>") < position("<Doh_Div>"));
+        assert!(position("<Period>2023</Period>") < position("</Doh_Div>"));
+        assert!(position("</Doh_Div>") < position("<Dividend>"));
+        assert!(xml.trim_end().ends_with("</body>\n</Envelope>"));
+        assert_eq!(xml.matches("<Dividend>").count(), 2);
+        assert_eq!(xml.matches("</Dividend>").count(), 2);
+
+        let (apple, johnson) = xml.split_once("</Dividend>").unwrap();
+        assert!(apple.contains("<Date>2023-05-18</Date>"));
+        assert!(apple.contains("<PayerTaxNumber>942404110</PayerTaxNumber>"));
+        assert!(
+            apple.contains("<PayerIdentificationNumber>US0378331005</PayerIdentificationNumber>")
+        );
+        assert!(apple.contains("<Value>123.46</Value>"));
+        assert!(apple.contains("<ForeignTax>18.52</ForeignTax>"));
+        assert!(apple.contains("<ReliefStatement>Article 10 of the convention</ReliefStatement>"));
+
+        assert!(!johnson.contains("<PayerTaxNumber>"));
+        assert!(!johnson.contains("<ReliefStatement>"));
+        assert!(johnson.contains("<PayerName>Johnson &amp; Johnson &lt;JNJ&gt;</PayerName>"));
+        assert!(johnson.contains(
+            "<PayerAddress>One Johnson &amp; Johnson Plaza, New Brunswick</PayerAddress>"
+        ));
+        assert!(johnson.contains("<Value>1.01</Value>"));
+        assert!(johnson.contains("<ForeignTax>0.00</ForeignTax>"));
+    }
+
+    #[test]
+    fn writes_doh_div_csv_rows() {
+        let csv = written(|output| write_csv(output, "12345679", &dividends()));
+        assert!(csv.contains("DOH-DIV;3.9;12345679;FO;O;;;;;;"));
+        let rows: Vec<_> = csv
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .skip(1)
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "18.05.2023;942404110;US0378331005;Apple Inc.;One Apple Park Way, Cupertino, CA 95014;US;1;123,46;18,52;US;Article 10 of the convention",
+                "06.06.2023;;US4781601046;Johnson & Johnson <JNJ>;One Johnson & Johnson Plaza, New Brunswick;US;1;1,01;0,00;US;",
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_xml_special_characters() {
+        assert_eq!(
+            escape_xml(r#"A&B <"C"> 'D'"#),
+            "A&amp;B &lt;&quot;C&quot;&gt; &apos;D&apos;"
+        );
+        assert_eq!(escape_xml("Nestlé"), "Nestlé");
+    }
+}
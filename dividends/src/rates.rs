@@ -0,0 +1,287 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{currency::minor_unit, money::Money};
+
+#[derive(Deserialize)]
+struct RatesByDay {
+    #[serde(rename = "$value")]
+    days: Vec<Rates>,
+}
+
+#[derive(Deserialize)]
+struct Rates {
+    #[serde(rename = "datum")]
+    date: String,
+    #[serde(rename = "$value")]
+    values: Vec<Rate>,
+}
+
+#[derive(Deserialize)]
+struct Rate {
+    #[serde(rename = "oznaka")]
+    name: String,
+    #[serde(rename = "$value")]
+    rate: Decimal,
+}
+
+/// How a rate source quotes its exchange rates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quotation {
+    /// Units of foreign currency for 1 EUR, as published by Banka Slovenije and the ECB.
+    ForeignPerEur,
+    /// EUR for 1 unit of foreign currency. No supported source quotes rates this way yet.
+    #[cfg(test)]
+    EurPerForeign,
+}
+
+/// Number of days a rate lookup may go back when no rate was published on the receipt date.
+pub const DEFAULT_MAX_LOOKBACK_DAYS: u32 = 10;
+
+pub struct RateTable {
+    quotation: Quotation,
+    days: BTreeMap<NaiveDate, HashMap<String, Decimal>>,
+    max_lookback_days: u32,
+}
+
+impl RateTable {
+    pub fn new(quotation: Quotation, days: BTreeMap<NaiveDate, HashMap<String, Decimal>>) -> Self {
+        Self {
+            quotation,
+            days,
+            max_lookback_days: DEFAULT_MAX_LOOKBACK_DAYS,
+        }
+    }
+
+    pub fn with_max_lookback_days(mut self, days: u32) -> Self {
+        self.max_lookback_days = days;
+        self
+    }
+
+    /// Loads the Banka Slovenije reference rate list (`DtecBS`).
+    pub fn load_bsi(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::from_bsi_reader(file)
+    }
+
+    pub fn from_bsi_reader(reader: impl Read) -> Result<Self> {
+        let rates: RatesByDay = serde_xml_rs::from_reader(reader)?;
+        let days = rates
+            .days
+            .into_iter()
+            .map(|day| {
+                let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+                    .with_context(|| format!("Invalid rate date {}", day.date))?;
+                let values = day
+                    .values
+                    .into_iter()
+                    .map(|rate| (rate.name, rate.rate))
+                    .collect();
+                Ok((date, values))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::new(Quotation::ForeignPerEur, days))
+    }
+
+    /// Returns the most recent rate published on or before `date`, as FURS requires for days
+    /// without a reference rate (weekends and holidays), looking back at most the configured
+    /// number of days.
+    pub fn rate(&self, date: NaiveDate, currency: &str) -> Option<Decimal> {
+        self.lookup(date, currency).map(|(_, rate)| rate)
+    }
+
+    /// Same as [`RateTable::rate`], also returning the date the rate was published on.
+    pub fn lookup(&self, date: NaiveDate, currency: &str) -> Option<(NaiveDate, Decimal)> {
+        let earliest = date - chrono::Duration::days(self.max_lookback_days.into());
+        let found = self
+            .days
+            .range(earliest..=date)
+            .rev()
+            .find_map(|(day, rates)| Some((*day, *rates.get(currency)?)));
+        let Some((rate_date, rate)) = found else {
+            log::debug!(
+                "No {currency} rate published between {earliest} and {date}, \
+                 the maximum look-back is {} days",
+                self.max_lookback_days
+            );
+            return None;
+        };
+        if rate_date != date {
+            log::warn!("No {currency} rate for {date}, using the rate from {rate_date}");
+        }
+        Some((rate_date, rate))
+    }
+
+    /// Converts a foreign amount into EUR, without rounding.
+    pub fn foreign_to_eur(&self, date: NaiveDate, money: &Money) -> Option<Money> {
+        let money = money.to_major_unit();
+        if money.is_eur() {
+            return Some(money);
+        }
+        let rate = self.rate(date, &money.currency)?;
+        let amount = match self.quotation {
+            Quotation::ForeignPerEur => money.amount / rate,
+            #[cfg(test)]
+            Quotation::EurPerForeign => money.amount * rate,
+        };
+        Some(Money::eur(amount))
+    }
+
+    /// Converts an amount in EUR into `currency`, without rounding. Minor-unit currencies are
+    /// converted through their major currency.
+    pub fn eur_to_foreign(&self, date: NaiveDate, money: &Money, currency: &str) -> Option<Money> {
+        if let Some(unit) = minor_unit(currency) {
+            let major = self.eur_to_foreign(date, money, unit.major)?;
+            return Some(Money::new(
+                major.amount * Decimal::from(unit.per_major),
+                currency,
+            ));
+        }
+        if currency == "EUR" {
+            return Some(money.clone());
+        }
+        let rate = self.rate(date, currency)?;
+        let amount = match self.quotation {
+            Quotation::ForeignPerEur => money.amount * rate,
+            #[cfg(test)]
+            Quotation::EurPerForeign => money.amount / rate,
+        };
+        Some(Money::new(amount, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bsi() -> RateTable {
+        RateTable::load_bsi(concat!(env!("CARGO_MANIFEST_DIR"), "/../rates.xml")).unwrap()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn money(value: &str, currency: &str) -> Money {
+        Money::parse(value, currency).unwrap()
+    }
+
+    #[test]
+    fn reads_bsi_rates_as_published() {
+        let rates = bsi();
+        assert_eq!(rates.quotation, Quotation::ForeignPerEur);
+        assert_eq!(
+            rates.rate(date("2023-01-02"), "USD"),
+            Some(Decimal::new(10683, 4))
+        );
+        assert_eq!(
+            rates.rate(date("2023-01-02"), "GBP"),
+            Some(Decimal::new(88630, 5))
+        );
+        assert_eq!(rates.rate(date("2023-01-01"), "USD"), None);
+    }
+
+    #[test]
+    fn falls_back_to_last_published_rate() {
+        let rates = bsi();
+        // Saturday and Sunday use Friday's rate.
+        assert_eq!(
+            rates.rate(date("2023-01-07"), "USD"),
+            Some(Decimal::new(10500, 4))
+        );
+        assert_eq!(
+            rates.rate(date("2023-01-08"), "USD"),
+            Some(Decimal::new(10500, 4))
+        );
+        // Easter Monday uses the rate from Good Friday.
+        assert_eq!(
+            rates.rate(date("2023-04-10"), "USD"),
+            Some(Decimal::new(10915, 4))
+        );
+        assert_eq!(
+            rates.rate(date("2023-01-09"), "USD"),
+            Some(Decimal::new(10696, 4))
+        );
+    }
+
+    #[test]
+    fn limits_look_back() {
+        let rates = bsi().with_max_lookback_days(1);
+        assert_eq!(rates.rate(date("2023-01-08"), "USD"), None);
+        assert_eq!(
+            rates.rate(date("2023-01-07"), "USD"),
+            Some(Decimal::new(10500, 4))
+        );
+    }
+
+    #[test]
+    fn converts_foreign_to_eur() {
+        let rates = bsi();
+        let eur = rates
+            .foreign_to_eur(date("2023-01-02"), &money("106.83", "USD"))
+            .unwrap();
+        assert_eq!(eur, money("100", "EUR"));
+        let eur = rates
+            .foreign_to_eur(date("2023-01-02"), &money("1.00", "USD"))
+            .unwrap();
+        assert_eq!(eur.round_cents(), money("0.94", "EUR"));
+        let eur = rates
+            .foreign_to_eur(date("2023-01-02"), &money("88.63", "GBP"))
+            .unwrap();
+        assert_eq!(eur, money("100", "EUR"));
+    }
+
+    #[test]
+    fn converts_eur_to_foreign() {
+        let rates = bsi();
+        let usd = rates
+            .eur_to_foreign(date("2023-01-02"), &money("100", "EUR"), "USD")
+            .unwrap();
+        assert_eq!(usd, money("106.83", "USD"));
+    }
+
+    #[test]
+    fn converts_minor_units_through_major_currency() {
+        let rates = bsi();
+        let pence = Money::new(Decimal::new(8863, 0), "GBX");
+        let eur = rates.foreign_to_eur(date("2023-01-02"), &pence).unwrap();
+        assert_eq!(eur, money("100", "EUR"));
+        let pence = rates
+            .eur_to_foreign(date("2023-01-02"), &money("100", "EUR"), "GBp")
+            .unwrap();
+        assert_eq!(pence, Money::new(Decimal::new(8863, 0), "GBp"));
+    }
+
+    #[test]
+    fn eur_is_not_converted() {
+        let rates = bsi();
+        let eur = money("12.5", "EUR");
+        assert_eq!(rates.foreign_to_eur(date("2023-01-01"), &eur), Some(eur));
+    }
+
+    #[test]
+    fn respects_eur_per_foreign_quotation() {
+        let days = BTreeMap::from([(
+            date("2023-01-02"),
+            HashMap::from([("USD".to_owned(), Decimal::new(5, 1))]),
+        )]);
+        let rates = RateTable::new(Quotation::EurPerForeign, days);
+        assert_eq!(
+            rates.foreign_to_eur(date("2023-01-02"), &money("10", "USD")),
+            Some(money("5", "EUR"))
+        );
+        assert_eq!(
+            rates.eur_to_foreign(date("2023-01-02"), &money("5", "EUR"), "USD"),
+            Some(money("10", "USD"))
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    isin::{isin_country, validate_isin},
+    Broker,
+};
+
+/// A ticker a security is listed under. Aliases without a broker apply to every broker.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickerAlias {
+    pub ticker: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broker: Option<Broker>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecurityType {
+    Stock,
+    Etf,
+    Adr,
+    Reit,
+    Fund,
+}
+
+/// A registry entry describing a security and the company paying its dividends.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Security {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isin: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tickers: Vec<TickerAlias>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer_country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer_tax_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lei: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_type: Option<SecurityType>,
+    /// Statement claiming relief under the double taxation convention for dividends of this
+    /// payer, written into the return as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relief: Option<String>,
+}
+
+impl Security {
+    pub fn name(&self) -> Option<&str> {
+        self.names.first().map(String::as_str)
+    }
+
+    /// The payer country, inferred from the ISIN prefix when the registry has none.
+    pub fn payer_country(&self) -> Option<&str> {
+        self.payer_country
+            .as_deref()
+            .or_else(|| self.isin.as_deref().and_then(isin_country))
+    }
+
+    /// The country the dividend originates from, the payer country unless stated otherwise.
+    pub fn source_country(&self) -> Option<&str> {
+        self.source_country
+            .as_deref()
+            .or_else(|| self.payer_country())
+    }
+
+    /// Warns about an invalid ISIN and about a payer country that disagrees with the ISIN
+    /// prefix.
+    fn check(&self) {
+        let Some(isin) = &self.isin else {
+            return;
+        };
+        if let Err(error) = validate_isin(isin) {
+            log::warn!("{error}");
+            return;
+        }
+        self.check_country(isin, self.name().unwrap_or("-"));
+    }
+
+    /// Warns when the payer country disagrees with the prefix of `isin`, which can also come
+    /// from a broker export for entries without one. ADRs are skipped, their ISIN belongs to the
+    /// depositary.
+    pub fn check_country(&self, isin: &str, name: &str) {
+        if self.security_type == Some(SecurityType::Adr) {
+            return;
+        }
+        if let (Some(country), Some(isin_country)) = (&self.payer_country, isin_country(isin)) {
+            if !country.eq_ignore_ascii_case(isin_country) {
+                log::warn!(
+                    "Payer country {country} of {isin} ({name}) differs from the ISIN country {isin_country}"
+                );
+            }
+        }
+    }
+
+    /// The country of an ISIN the registry entry does not know itself, unless the security is
+    /// an ADR.
+    pub fn export_isin_country<'i>(&self, isin: &'i str) -> Option<&'i str> {
+        if self.isin.is_some() || self.security_type == Some(SecurityType::Adr) {
+            return None;
+        }
+        isin_country(isin)
+    }
+}
+
+/// A security as it appears in a broker export that carries ISINs.
+pub struct Listing {
+    pub broker: Broker,
+    pub ticker: String,
+    pub isin: String,
+    pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Learned {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+/// Payer data keyed by ISIN, with ticker aliases for brokers that only export tickers.
+#[derive(Default)]
+pub struct Registry {
+    securities: Vec<Security>,
+    by_isin: HashMap<String, usize>,
+    by_ticker: HashMap<String, Vec<usize>>,
+}
+
+impl Registry {
+    /// Loads a registry file, warning about duplicate and conflicting entries and about
+    /// countries that disagree with the ISIN.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let securities: Vec<Security> = read_json(path.as_ref())?;
+        let mut registry = Registry::default();
+        for security in securities {
+            security.check();
+            registry.insert(security);
+        }
+        Ok(registry)
+    }
+
+    /// Builds the registry from the legacy `places.json` (ticker → address, country) and
+    /// `revolut.json` (Revolut ticker → ISIN, name) files.
+    pub fn load_legacy(places: impl AsRef<Path>, revolut_info: impl AsRef<Path>) -> Result<Self> {
+        let places: HashMap<String, (String, String)> = read_json(places.as_ref())?;
+        let revolut_info: HashMap<String, (String, String)> = read_json(revolut_info.as_ref())?;
+
+        let mut registry = Registry::default();
+        for (ticker, (isin, name)) in &revolut_info {
+            let mut tickers = vec![TickerAlias {
+                ticker: ticker.clone(),
+                broker: Some(Broker::Revolut),
+                exchange: None,
+            }];
+            let place = places.get(ticker);
+            if place.is_some() {
+                tickers.push(TickerAlias {
+                    ticker: ticker.clone(),
+                    broker: None,
+                    exchange: None,
+                });
+            }
+            registry.insert(Security {
+                isin: Some(isin.clone()),
+                names: vec![name.clone()],
+                address: place.map(|(address, _)| address.clone()),
+                payer_country: place.map(|(_, country)| country.clone()),
+                tickers,
+                ..Default::default()
+            });
+        }
+        for (ticker, (address, country)) in places {
+            if revolut_info.contains_key(&ticker) {
+                continue;
+            }
+            registry.insert(Security {
+                address: Some(address),
+                payer_country: Some(country),
+                tickers: vec![TickerAlias {
+                    ticker,
+                    broker: None,
+                    exchange: None,
+                }],
+                ..Default::default()
+            });
+        }
+        Ok(registry)
+    }
+
+    /// Writes the registry sorted by ISIN, then by ticker for entries without one.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut securities: Vec<_> = self.securities.iter().collect();
+        securities.sort_by_key(|security| {
+            (
+                security.isin.is_none(),
+                security.isin.clone(),
+                security.tickers.first().map(|alias| alias.ticker.clone()),
+            )
+        });
+        let file =
+            File::create(path).with_context(|| format!("Unable to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &securities)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Adds an entry. A second entry for a known ISIN is dropped with a warning, telling exact
+    /// duplicates apart from conflicting entries, and ticker aliases claimed by another security
+    /// are reported.
+    pub fn insert(&mut self, security: Security) {
+        if let Some(isin) = &security.isin {
+            if let Some(&existing) = self.by_isin.get(isin) {
+                if self.securities[existing] == security {
+                    log::warn!("Duplicate registry entry for {isin}");
+                } else {
+                    log::warn!("Conflicting registry entries for {isin}, keeping the first one");
+                }
+                return;
+            }
+        }
+
+        let index = self.securities.len();
+        for alias in &security.tickers {
+            let entries = self.by_ticker.entry(alias.ticker.clone()).or_default();
+            let conflict = entries
+                .iter()
+                .filter(|&&other| other != index)
+                .any(|&other| {
+                    self.securities[other].tickers.iter().any(|other| {
+                        other.ticker == alias.ticker
+                            && other.broker == alias.broker
+                            && other.exchange == alias.exchange
+                    })
+                });
+            if conflict {
+                log::warn!(
+                    "Ticker {} is registered for several securities, it will not be resolved",
+                    alias.ticker
+                );
+            }
+            if !entries.contains(&index) {
+                entries.push(index);
+            }
+        }
+        if let Some(isin) = &security.isin {
+            self.by_isin.insert(isin.clone(), index);
+        }
+        self.securities.push(security);
+    }
+
+    /// Replaces the entry with the same ISIN, or the ISIN-less entry sharing a ticker alias,
+    /// inserting the security when neither exists.
+    pub fn upsert(&mut self, security: Security) {
+        let existing = match &security.isin {
+            Some(isin) => self.by_isin.get(isin).copied(),
+            None => None,
+        }
+        .or_else(|| {
+            self.securities.iter().position(|existing| {
+                existing.isin.is_none()
+                    && existing
+                        .tickers
+                        .iter()
+                        .any(|alias| security.tickers.contains(alias))
+            })
+        });
+        let Some(existing) = existing else {
+            self.insert(security);
+            return;
+        };
+
+        let mut securities = std::mem::take(&mut self.securities);
+        securities[existing] = security;
+        *self = Registry::default();
+        for security in securities {
+            self.insert(security);
+        }
+    }
+
+    /// Records a ticker, ISIN and name combination seen in a broker export. Tickers are learned
+    /// as broker independent aliases unless another security already claims them.
+    pub fn learn(&mut self, listing: &Listing) -> Learned {
+        let existing = self.by_isin(&listing.isin).cloned().or_else(|| {
+            self.securities
+                .iter()
+                .find(|security| {
+                    security.isin.is_none()
+                        && security
+                            .tickers
+                            .iter()
+                            .any(|alias| alias.ticker == listing.ticker)
+                })
+                .cloned()
+        });
+        let learned = if existing.is_some() {
+            Learned::Updated
+        } else {
+            Learned::Added
+        };
+        let mut security = existing.unwrap_or_default();
+        let before = security.clone();
+
+        security.isin = Some(listing.isin.clone());
+        if !listing.name.is_empty() && !security.names.contains(&listing.name) {
+            security.names.push(listing.name.clone());
+        }
+        if !security
+            .tickers
+            .iter()
+            .any(|alias| alias.ticker == listing.ticker)
+        {
+            let claimed = self.by_ticker.get(&listing.ticker).is_some_and(|others| {
+                others
+                    .iter()
+                    .any(|&other| self.securities[other].isin.as_ref() != Some(&listing.isin))
+            });
+            security.tickers.push(TickerAlias {
+                ticker: listing.ticker.clone(),
+                broker: claimed.then_some(listing.broker),
+                exchange: None,
+            });
+        }
+
+        if security == before {
+            return Learned::Unchanged;
+        }
+        self.upsert(security);
+        learned
+    }
+
+    pub fn securities(&self) -> impl Iterator<Item = &Security> {
+        self.securities.iter()
+    }
+
+    pub fn by_isin(&self, isin: &str) -> Option<&Security> {
+        self.by_isin.get(isin).map(|&index| &self.securities[index])
+    }
+
+    /// Finds the security listed under `ticker` at `broker`. Aliases for that broker win over
+    /// broker independent ones; a ticker shared by several securities is not resolved.
+    pub fn by_ticker(&self, ticker: &str, broker: Broker) -> Option<&Security> {
+        let candidates = self.by_ticker.get(ticker)?;
+        for broker in [Some(broker), None] {
+            let matching: Vec<_> = candidates
+                .iter()
+                .map(|&index| &self.securities[index])
+                .filter(|security| {
+                    security
+                        .tickers
+                        .iter()
+                        .any(|alias| alias.ticker == ticker && alias.broker == broker)
+                })
+                .collect();
+            match matching.as_slice() {
+                [] => continue,
+                [security] => return Some(security),
+                _ => {
+                    log::warn!("Ticker {ticker} is ambiguous in the registry");
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    /// Resolves a security by ISIN when the broker exports one, falling back to the ticker. A
+    /// ticker match is rejected if the registry knows a different ISIN for it.
+    pub fn resolve(&self, isin: Option<&str>, ticker: &str, broker: Broker) -> Option<&Security> {
+        let Some(isin) = isin else {
+            return self.by_ticker(ticker, broker);
+        };
+        if let Some(security) = self.by_isin(isin) {
+            return Some(security);
+        }
+        self.by_ticker(ticker, broker)
+            .filter(|security| security.isin.as_deref().is_none_or(|known| known == isin))
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents =
+        read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Unable to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REALTY_INCOME: &str = "US7561091049";
+    const OWENS_CORNING: &str = "US6907421019";
+    const ATT: &str = "US00206R1023";
+    const TELUS: &str = "CA87971M1032";
+
+    fn alias(ticker: &str, broker: Option<Broker>) -> TickerAlias {
+        TickerAlias {
+            ticker: ticker.to_owned(),
+            broker,
+            exchange: None,
+        }
+    }
+
+    fn security(isin: &str, name: &str, tickers: Vec<TickerAlias>) -> Security {
+        Security {
+            isin: Some(isin.to_owned()),
+            names: vec![name.to_owned()],
+            tickers,
+            ..Default::default()
+        }
+    }
+
+    fn registry(securities: Vec<Security>) -> Registry {
+        let mut registry = Registry::default();
+        for security in securities {
+            registry.insert(security);
+        }
+        registry
+    }
+
+    fn isin(security: Option<&Security>) -> Option<&str> {
+        security.and_then(|security| security.isin.as_deref())
+    }
+
+    #[test]
+    fn prefers_broker_aliases_over_broker_independent_ones() {
+        // Revolut lists Owens Corning as O, which everyone else uses for Realty Income.
+        let registry = registry(vec![
+            security(REALTY_INCOME, "Realty Income", vec![alias("O", None)]),
+            security(
+                OWENS_CORNING,
+                "Owens Corning",
+                vec![alias("O", Some(Broker::Revolut))],
+            ),
+        ]);
+        assert_eq!(
+            isin(registry.by_ticker("O", Broker::Revolut)),
+            Some(OWENS_CORNING)
+        );
+        assert_eq!(
+            isin(registry.by_ticker("O", Broker::Trading212)),
+            Some(REALTY_INCOME)
+        );
+        assert_eq!(registry.by_ticker("OC", Broker::Revolut), None);
+    }
+
+    #[test]
+    fn does_not_resolve_ambiguous_tickers() {
+        let registry = registry(vec![
+            security(ATT, "AT&T", vec![alias("T", None)]),
+            security(TELUS, "Telus", vec![alias("T", None)]),
+        ]);
+        assert_eq!(registry.by_ticker("T", Broker::Revolut), None);
+        // The ISIN still tells them apart.
+        assert_eq!(
+            isin(registry.resolve(Some(TELUS), "T", Broker::Degiro)),
+            Some(TELUS)
+        );
+    }
+
+    #[test]
+    fn rejects_ticker_matches_with_another_isin() {
+        let att = registry(vec![security(ATT, "AT&T", vec![alias("T", None)])]);
+        assert_eq!(isin(att.resolve(None, "T", Broker::Revolut)), Some(ATT));
+        assert_eq!(
+            isin(att.resolve(Some(ATT), "T", Broker::Revolut)),
+            Some(ATT)
+        );
+        assert_eq!(att.resolve(Some(TELUS), "T", Broker::Revolut), None);
+
+        // Entries without an ISIN accept any.
+        let without_isin = registry(vec![Security {
+            address: Some("208 S. Akard St., Dallas".to_owned()),
+            tickers: vec![alias("T", None)],
+            ..Default::default()
+        }]);
+        assert!(without_isin
+            .resolve(Some(TELUS), "T", Broker::Revolut)
+            .is_some());
+    }
+
+    #[test]
+    fn learns_broker_aliases_for_claimed_tickers() {
+        let mut registry = registry(vec![security(
+            REALTY_INCOME,
+            "Realty Income",
+            vec![alias("O", None)],
+        )]);
+        let listing = Listing {
+            broker: Broker::Revolut,
+            ticker: "O".to_owned(),
+            isin: OWENS_CORNING.to_owned(),
+            name: "Owens Corning".to_owned(),
+        };
+        assert_eq!(registry.learn(&listing), Learned::Added);
+        assert_eq!(registry.learn(&listing), Learned::Unchanged);
+
+        let learned = registry.by_isin(OWENS_CORNING).unwrap();
+        assert_eq!(learned.tickers, [alias("O", Some(Broker::Revolut))]);
+        assert_eq!(
+            isin(registry.by_ticker("O", Broker::Revolut)),
+            Some(OWENS_CORNING)
+        );
+        assert_eq!(
+            isin(registry.by_ticker("O", Broker::Trading212)),
+            Some(REALTY_INCOME)
+        );
+
+        // Unclaimed tickers are learned for every broker.
+        let listing = Listing {
+            broker: Broker::Revolut,
+            ticker: "T".to_owned(),
+            isin: ATT.to_owned(),
+            name: "AT&T".to_owned(),
+        };
+        assert_eq!(registry.learn(&listing), Learned::Added);
+        assert_eq!(registry.by_isin(ATT).unwrap().tickers, [alias("T", None)]);
+    }
+}
@@ -0,0 +1,228 @@
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+use crate::Dividend;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    Payer,
+    Country,
+    Month,
+}
+
+impl Grouping {
+    /// What the dividends are grouped by. Payers are grouped by their ISIN, brokers name the
+    /// same payer differently.
+    fn key(self, dividend: &Dividend) -> String {
+        match self {
+            Grouping::Payer => dividend.payer_id.clone(),
+            Grouping::Country => dividend.source_country.clone(),
+            Grouping::Month => dividend.date.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// How a group is shown, payers by the name of their first dividend.
+    fn label(self, key: String, dividends: &[&Dividend]) -> String {
+        match self {
+            Grouping::Payer => format!("{} ({key})", dividends[0].name),
+            Grouping::Country | Grouping::Month => key,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+/// Totals of the dividends sharing a payer, source country or month, in EUR.
+#[derive(Serialize)]
+pub struct SummaryRow {
+    pub group: Grouping,
+    pub key: String,
+    pub rows: usize,
+    pub gross: Decimal,
+    pub withheld: Decimal,
+    /// Foreign tax claimed in the return.
+    pub foreign_tax: Decimal,
+    /// Withheld tax as a percentage of the gross amount.
+    pub withholding_percent: Decimal,
+}
+
+pub fn summarize(dividends: &[Dividend], grouping: Grouping) -> Vec<SummaryRow> {
+    let mut groups: BTreeMap<String, Vec<&Dividend>> = BTreeMap::new();
+    for dividend in dividends {
+        groups
+            .entry(grouping.key(dividend))
+            .or_default()
+            .push(dividend);
+    }
+
+    let mut rows: Vec<_> = groups
+        .into_iter()
+        .map(|(key, dividends)| {
+            let gross: Decimal = dividends.iter().map(|d| d.amount.amount).sum();
+            let withheld: Decimal = dividends.iter().map(|d| d.withheld.amount).sum();
+            let foreign_tax = dividends.iter().map(|d| d.tax.amount).sum();
+            let withholding_percent = if gross.is_zero() {
+                Decimal::ZERO
+            } else {
+                withheld / gross * Decimal::ONE_HUNDRED
+            };
+            SummaryRow {
+                group: grouping,
+                key: grouping.label(key, &dividends),
+                rows: dividends.len(),
+                gross: cents(gross),
+                withheld: cents(withheld),
+                foreign_tax: cents(foreign_tax),
+                withholding_percent: cents(withholding_percent),
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.key.cmp(&b.key));
+    rows
+}
+
+/// Rounds to two decimals, keeping the trailing zeros in CSV and JSON output.
+fn cents(value: Decimal) -> Decimal {
+    let mut value = value.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    value.rescale(2);
+    value
+}
+
+pub fn write_summary(
+    format: ReportFormat,
+    output: &mut impl Write,
+    rows: &[SummaryRow],
+) -> Result<()> {
+    match format {
+        ReportFormat::Table => write_table(output, rows),
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(output);
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *output, rows)?;
+            writeln!(output)?;
+            Ok(())
+        }
+    }
+}
+
+fn write_table(output: &mut impl Write, rows: &[SummaryRow]) -> Result<()> {
+    let mut group = None;
+    for row in rows {
+        if group != Some(row.group) {
+            if group.is_some() {
+                writeln!(output)?;
+            }
+            group = Some(row.group);
+            writeln!(
+                output,
+                "{:<40}  {:>5}  {:>10}  {:>10}  {:>10}  {:>7}",
+                format!("{:?}", row.group),
+                "Rows",
+                "Gross",
+                "Withheld",
+                "Claimed",
+                "Rate"
+            )?;
+        }
+        writeln!(
+            output,
+            "{:<40.40}  {:>5}  {:>10.2}  {:>10.2}  {:>10.2}  {:>6.2}%",
+            row.key, row.rows, row.gross, row.withheld, row.foreign_tax, row.withholding_percent
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{money::Money, withholding::Withholding};
+
+    fn dividend(payer_id: &str, name: &str, month: u32, gross: &str, withheld: &str) -> Dividend {
+        let eur = |value: &str| Money::eur(value.parse().unwrap());
+        Dividend {
+            date: NaiveDate::from_ymd_opt(2023, month, 15).unwrap(),
+            payer_tax_number: None,
+            payer_id: payer_id.to_owned(),
+            name: name.to_owned(),
+            address: "One Apple Park Way, Cupertino, CA 95014".to_owned(),
+            payer_country: "US".to_owned(),
+            source_country: "US".to_owned(),
+            amount: eur(gross),
+            tax: eur(withheld),
+            withheld: eur(withheld),
+            withholding: Withholding::Reported,
+            relief: None,
+        }
+    }
+
+    fn dividends() -> Vec<Dividend> {
+        vec![
+            dividend("US0378331005", "Apple Inc.", 5, "1.5", "0.225"),
+            dividend("US0378331005", "APPLE INC", 8, "2", "0.3"),
+            dividend("US1912161007", "Coca-Cola", 5, "0", "0"),
+        ]
+    }
+
+    #[test]
+    fn groups_payers_by_isin() {
+        let rows = summarize(&dividends(), Grouping::Payer);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].key, "Apple Inc. (US0378331005)");
+        assert_eq!(rows[0].rows, 2);
+        assert_eq!(rows[0].gross.to_string(), "3.50");
+        assert_eq!(rows[0].withheld.to_string(), "0.53");
+        assert_eq!(rows[0].withholding_percent.to_string(), "15.00");
+
+        // A payer without gross dividends has no withholding rate.
+        assert_eq!(rows[1].key, "Coca-Cola (US1912161007)");
+        assert_eq!(rows[1].withholding_percent.to_string(), "0.00");
+    }
+
+    #[test]
+    fn groups_by_month() {
+        let rows = summarize(&dividends(), Grouping::Month);
+        let keys: Vec<_> = rows
+            .iter()
+            .map(|row| (row.key.as_str(), row.rows))
+            .collect();
+        assert_eq!(keys, [("2023-05", 2), ("2023-08", 1)]);
+    }
+
+    #[test]
+    fn writes_two_decimals_to_csv_and_json() {
+        let rows = summarize(&dividends(), Grouping::Country);
+        let write = |format| {
+            let mut output = vec![];
+            write_summary(format, &mut output, &rows).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(
+            write(ReportFormat::Csv),
+            "group,key,rows,gross,withheld,foreign_tax,withholding_percent\n\
+             country,US,3,3.50,0.53,0.53,15.00\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&write(ReportFormat::Json)).unwrap();
+        assert_eq!(json[0]["group"], "country");
+        assert_eq!(json[0]["gross"], "3.50");
+        assert_eq!(json[0]["withholding_percent"], "15.00");
+    }
+}
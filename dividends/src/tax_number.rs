@@ -0,0 +1,54 @@
+use anyhow::{bail, Result};
+
+/// Validates a Slovenian tax number (davčna številka): eight digits, the first one non-zero, and
+/// the last one a modulo 11 check digit over the first seven, weighted 8 to 2.
+pub fn validate_tax_number(tax_number: &str) -> Result<()> {
+    let digits: Vec<u32> = tax_number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 8 || tax_number.len() != 8 {
+        bail!("Tax number {tax_number} must consist of exactly 8 digits");
+    }
+    if digits[0] == 0 {
+        bail!("Tax number {tax_number} must not start with 0");
+    }
+
+    let sum: u32 = digits[..7]
+        .iter()
+        .zip((2..=8).rev())
+        .map(|(digit, weight)| digit * weight)
+        .sum();
+    let check = match 11 - sum % 11 {
+        11 => bail!("Tax number {tax_number} is not valid"),
+        10 => 0,
+        check => check,
+    };
+    if digits[7] != check {
+        bail!("Tax number {tax_number} has an invalid check digit");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_tax_numbers() {
+        assert!(validate_tax_number("12345679").is_ok());
+        // 11 - 1 = 10 wraps around to a check digit of 0.
+        assert!(validate_tax_number("10000020").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_tax_numbers() {
+        assert!(validate_tax_number("12345678").is_err());
+        assert!(validate_tax_number("10000021").is_err());
+        // A remainder of 0 would need a check digit of 11, no tax number has one.
+        for check in 0..=9 {
+            assert!(validate_tax_number(&format!("1000007{check}")).is_err());
+        }
+        assert!(validate_tax_number("02345679").is_err());
+        assert!(validate_tax_number("1234567").is_err());
+        assert!(validate_tax_number("1234567a").is_err());
+        assert!(validate_tax_number("1234 5679").is_err());
+    }
+}
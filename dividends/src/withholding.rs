@@ -0,0 +1,77 @@
+use std::fmt;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::money::Money;
+
+/// How the foreign tax of a dividend was determined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Withholding {
+    /// The broker reported the withheld tax.
+    Reported,
+    /// The broker only reported the net amount, the tax was estimated at this rate.
+    Estimated(Decimal),
+    /// The broker only reported the net amount and the source country has no known rate, so no
+    /// tax is assumed.
+    Unknown,
+}
+
+impl fmt::Display for Withholding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Withholding::Reported => write!(f, "reported"),
+            Withholding::Estimated(rate) => {
+                write!(
+                    f,
+                    "estimated at {}%",
+                    (rate * Decimal::ONE_HUNDRED).normalize()
+                )
+            }
+            Withholding::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Splits a net dividend into the gross amount and the tax withheld at `rate`, both in cents.
+pub fn gross_up(net: &Money, rate: Decimal) -> (Money, Money) {
+    let gross = (net.amount / (Decimal::ONE - rate))
+        .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    let tax = gross - net.amount;
+    (
+        Money::new(gross, net.currency.clone()),
+        Money::new(tax, net.currency.clone()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usd(amount: i64) -> Money {
+        Money::new(Decimal::new(amount, 2), "USD")
+    }
+
+    fn rate(percent: &str) -> Decimal {
+        percent.parse::<Decimal>().unwrap() / Decimal::ONE_HUNDRED
+    }
+
+    #[test]
+    fn grosses_up_net_dividends_to_cents() {
+        let cases = [
+            (85, "15", 100, 15),
+            (850, "15", 1000, 150),
+            (100, "15", 118, 18),
+            (1, "15", 1, 0),
+            (70, "30", 100, 30),
+            (123, "26.375", 167, 44),
+            (100, "0", 100, 0),
+        ];
+        for (net, percent, gross, tax) in cases {
+            assert_eq!(
+                gross_up(&usd(net), rate(percent)),
+                (usd(gross), usd(tax)),
+                "{net} cents net at {percent}%"
+            );
+        }
+    }
+}